@@ -1,6 +1,6 @@
 extern crate libc;
 
-use libc::{c_char, c_int, c_double, size_t, c_void};
+use libc::{c_char, c_int, c_double, c_float, size_t, c_void};
 
 #[repr(C)]
 pub enum CEXR_PixelType {
@@ -78,6 +78,71 @@ extern "C" {
                                    name: *const c_char)
                                    -> CEXR_Channel;
     pub fn CEXR_Header_new_channel_iterator(header: *const CEXR_Header) -> CEXR_ChannelIterator;
+
+    pub fn CEXR_Header_set_string_attribute(header: *mut CEXR_Header,
+                                            name: *const c_char,
+                                            value: *const c_char);
+    pub fn CEXR_Header_get_string_attribute(header: *const CEXR_Header,
+                                            name: *const c_char)
+                                            -> *const c_char;
+    pub fn CEXR_Header_set_float_attribute(header: *mut CEXR_Header, name: *const c_char, value: f32);
+    pub fn CEXR_Header_get_float_attribute(header: *const CEXR_Header,
+                                           name: *const c_char,
+                                           out: *mut f32)
+                                           -> bool;
+    pub fn CEXR_Header_set_int_attribute(header: *mut CEXR_Header, name: *const c_char, value: c_int);
+    pub fn CEXR_Header_get_int_attribute(header: *const CEXR_Header,
+                                         name: *const c_char,
+                                         out: *mut c_int)
+                                         -> bool;
+    pub fn CEXR_Header_attribute_iter(header: *const CEXR_Header) -> *mut CEXR_AttributeIter;
+    pub fn CEXR_Header_erase_attribute(header: *mut CEXR_Header, name: *const c_char);
+    pub fn CEXR_Header_set_compression(header: *mut CEXR_Header, compression: CEXR_CompressionMethod);
+    pub fn CEXR_Header_compression(header: *const CEXR_Header) -> CEXR_CompressionMethod;
+
+    pub fn CEXR_Header_set_tile_description(header: *mut CEXR_Header,
+                                             x_size: c_int,
+                                             y_size: c_int,
+                                             level_mode: c_int,
+                                             rounding_mode: c_int);
+    pub fn CEXR_Header_has_tile_description(header: *const CEXR_Header) -> bool;
+    pub fn CEXR_Header_tile_description(header: *const CEXR_Header,
+                                         x_size_out: *mut c_int,
+                                         y_size_out: *mut c_int,
+                                         level_mode_out: *mut c_int,
+                                         rounding_mode_out: *mut c_int);
+
+    // NOTE: `CEXR_Box2i` and `CEXR_V2f` are defined alongside the window/
+    // screen-window-center accessors; see cexr_type_aliases for the Rust
+    // aliases (`Box2i` and the `(x, y)` tuple used for `V2f`).
+    pub fn CEXR_Header_set_box2i_attribute(header: *mut CEXR_Header,
+                                           name: *const c_char,
+                                           value: CEXR_Box2i);
+    pub fn CEXR_Header_get_box2i_attribute(header: *const CEXR_Header,
+                                           name: *const c_char,
+                                           out: *mut CEXR_Box2i)
+                                           -> bool;
+    pub fn CEXR_Header_set_v2f_attribute(header: *mut CEXR_Header, name: *const c_char, value: CEXR_V2f);
+    pub fn CEXR_Header_get_v2f_attribute(header: *const CEXR_Header,
+                                         name: *const c_char,
+                                         out: *mut CEXR_V2f)
+                                         -> bool;
+}
+
+// ------------------------------------------------------------------------------
+// Attribute iterator
+#[repr(C)]
+pub struct CEXR_AttributeIter {
+    begin: *mut c_void,
+    end: *mut c_void,
+}
+
+extern "C" {
+    pub fn CEXR_AttributeIter_delete(iterator: *mut CEXR_AttributeIter);
+    pub fn CEXR_AttributeIter_next(iterator: *mut CEXR_AttributeIter,
+                                   name_out: *mut *const c_char,
+                                   type_out: *mut *const c_char)
+                                   -> bool;
 }
 
 
@@ -139,6 +204,229 @@ extern "C" {
 }
 
 
+// ------------------------------------------------------------------------------
+// TiledOutputFile
+#[repr(C)]
+pub struct CEXR_TiledOutputFile {
+    tiled_output_file: *mut c_void,
+}
+
+extern "C" {
+    pub fn CEXR_TiledOutputFile_from_stream(ostream: *mut CEXR_OStream,
+                                            header: *const CEXR_Header,
+                                            num_threads: c_int,
+                                            out: *mut *mut CEXR_TiledOutputFile,
+                                            error_out: *mut *const c_char)
+                                            -> c_int;
+    pub fn CEXR_TiledOutputFile_delete(tiled_output_file: *mut CEXR_TiledOutputFile);
+    pub fn CEXR_TiledOutputFile_header(tiled_output_file: *const CEXR_TiledOutputFile) -> *const CEXR_Header;
+    pub fn CEXR_TiledOutputFile_set_framebuffer(tiled_output_file: *mut CEXR_TiledOutputFile,
+                                                frame_buffer: *const CEXR_FrameBuffer,
+                                                error_out: *mut *const c_char)
+                                                -> c_int;
+    pub fn CEXR_TiledOutputFile_write_tile(tiled_output_file: *mut CEXR_TiledOutputFile,
+                                           dx: c_int,
+                                           dy: c_int,
+                                           level_x: c_int,
+                                           level_y: c_int,
+                                           error_out: *mut *const c_char)
+                                           -> c_int;
+    pub fn CEXR_TiledOutputFile_level_dimensions(tiled_output_file: *const CEXR_TiledOutputFile,
+                                                 level_x: c_int,
+                                                 level_y: c_int,
+                                                 x_out: *mut c_int,
+                                                 y_out: *mut c_int);
+    pub fn CEXR_TiledOutputFile_num_x_levels(tiled_output_file: *const CEXR_TiledOutputFile) -> c_int;
+    pub fn CEXR_TiledOutputFile_num_y_levels(tiled_output_file: *const CEXR_TiledOutputFile) -> c_int;
+    pub fn CEXR_TiledOutputFile_level_data_window(tiled_output_file: *const CEXR_TiledOutputFile,
+                                                  level_x: c_int,
+                                                  level_y: c_int)
+                                                  -> CEXR_Box2i;
+}
+
+
+// ------------------------------------------------------------------------------
+// TiledInputFile
+#[repr(C)]
+pub struct CEXR_TiledInputFile {
+    header: CEXR_Header,
+    tiled_input_file: *mut c_void,
+}
+
+extern "C" {
+    pub fn CEXR_TiledInputFile_from_stream(istream: *mut CEXR_IStream,
+                                           num_threads: c_int,
+                                           out: *mut *mut CEXR_TiledInputFile,
+                                           error_out: *mut *const c_char)
+                                           -> c_int;
+    pub fn CEXR_TiledInputFile_delete(tiled_input_file: *mut CEXR_TiledInputFile);
+    pub fn CEXR_TiledInputFile_header(tiled_input_file: *const CEXR_TiledInputFile) -> *const CEXR_Header;
+    pub fn CEXR_TiledInputFile_set_framebuffer(tiled_input_file: *mut CEXR_TiledInputFile,
+                                               frame_buffer: *mut CEXR_FrameBuffer,
+                                               error_out: *mut *const c_char)
+                                               -> c_int;
+    pub fn CEXR_TiledInputFile_read_tile(tiled_input_file: *mut CEXR_TiledInputFile,
+                                         dx: c_int,
+                                         dy: c_int,
+                                         level_x: c_int,
+                                         level_y: c_int,
+                                         error_out: *mut *const c_char)
+                                         -> c_int;
+    pub fn CEXR_TiledInputFile_level_dimensions(tiled_input_file: *const CEXR_TiledInputFile,
+                                                level_x: c_int,
+                                                level_y: c_int,
+                                                x_out: *mut c_int,
+                                                y_out: *mut c_int);
+    pub fn CEXR_TiledInputFile_num_x_levels(tiled_input_file: *const CEXR_TiledInputFile) -> c_int;
+    pub fn CEXR_TiledInputFile_num_y_levels(tiled_input_file: *const CEXR_TiledInputFile) -> c_int;
+    pub fn CEXR_TiledInputFile_level_data_window(tiled_input_file: *const CEXR_TiledInputFile,
+                                                 level_x: c_int,
+                                                 level_y: c_int)
+                                                 -> CEXR_Box2i;
+}
+
+extern "C" {
+    pub fn CEXR_set_global_thread_count(num_threads: c_int, error_out: *mut *const c_char) -> c_int;
+}
+
+
+// ------------------------------------------------------------------------------
+// DeepScanLineInputFile
+#[repr(C)]
+pub struct CEXR_DeepScanLineInputFile {
+    header: CEXR_Header,
+    deep_scan_line_input_file: *mut c_void,
+}
+
+extern "C" {
+    pub fn CEXR_DeepScanLineInputFile_from_stream(istream: *mut CEXR_IStream,
+                                                  num_threads: c_int,
+                                                  out: *mut *mut CEXR_DeepScanLineInputFile,
+                                                  error_out: *mut *const c_char)
+                                                  -> c_int;
+    pub fn CEXR_DeepScanLineInputFile_delete(deep_scan_line_input_file: *mut CEXR_DeepScanLineInputFile);
+    pub fn CEXR_DeepScanLineInputFile_header(deep_scan_line_input_file: *const CEXR_DeepScanLineInputFile) -> *const CEXR_Header;
+
+    // Fills `counts_out`, a caller-allocated array of `width * height`
+    // entries in data-window row-major order, with the number of deep
+    // samples stored at each pixel.
+    pub fn CEXR_DeepScanLineInputFile_read_pixel_sample_counts(deep_scan_line_input_file: *mut CEXR_DeepScanLineInputFile,
+                                                               counts_out: *mut c_int,
+                                                               error_out: *mut *const c_char)
+                                                               -> c_int;
+
+    // Reads channel `name`'s deep samples for every pixel into the
+    // per-pixel sample arrays pointed to by `sample_pointers`, which must
+    // have one entry per pixel (in the same order as
+    // `read_pixel_sample_counts`), each pointing at a buffer at least as
+    // large as that pixel's sample count.
+    pub fn CEXR_DeepScanLineInputFile_read_channel(deep_scan_line_input_file: *mut CEXR_DeepScanLineInputFile,
+                                                   name: *const c_char,
+                                                   sample_pointers: *mut *mut c_float,
+                                                   error_out: *mut *const c_char)
+                                                   -> c_int;
+}
+
+
+// ------------------------------------------------------------------------------
+// DeepScanLineOutputFile
+#[repr(C)]
+pub struct CEXR_DeepScanLineOutputFile {
+    deep_scan_line_output_file: *mut c_void,
+}
+
+extern "C" {
+    pub fn CEXR_DeepScanLineOutputFile_from_stream(ostream: *mut CEXR_OStream,
+                                                   header: *const CEXR_Header,
+                                                   num_threads: c_int,
+                                                   out: *mut *mut CEXR_DeepScanLineOutputFile,
+                                                   error_out: *mut *const c_char)
+                                                   -> c_int;
+    pub fn CEXR_DeepScanLineOutputFile_delete(deep_scan_line_output_file: *mut CEXR_DeepScanLineOutputFile);
+    pub fn CEXR_DeepScanLineOutputFile_header(deep_scan_line_output_file: *const CEXR_DeepScanLineOutputFile) -> *const CEXR_Header;
+
+    // Sets the per-pixel sample counts (data-window row-major order,
+    // width * height entries) that the next write_channel calls' sample
+    // arrays are sized according to.
+    pub fn CEXR_DeepScanLineOutputFile_set_pixel_sample_counts(deep_scan_line_output_file: *mut CEXR_DeepScanLineOutputFile,
+                                                               counts: *const c_int,
+                                                               error_out: *mut *const c_char)
+                                                               -> c_int;
+
+    // Writes channel `name`'s deep samples for every pixel, reading from
+    // the per-pixel sample arrays pointed to by `sample_pointers`, laid
+    // out the same way as `CEXR_DeepScanLineInputFile_read_channel`.
+    pub fn CEXR_DeepScanLineOutputFile_write_channel(deep_scan_line_output_file: *mut CEXR_DeepScanLineOutputFile,
+                                                     name: *const c_char,
+                                                     sample_pointers: *const *const c_float,
+                                                     error_out: *mut *const c_char)
+                                                     -> c_int;
+}
+
+
+// ------------------------------------------------------------------------------
+// MultiPartInputFile
+#[repr(C)]
+pub struct CEXR_MultiPartInputFile {
+    multi_part_input_file: *mut c_void,
+}
+
+extern "C" {
+    pub fn CEXR_MultiPartInputFile_from_stream(istream: *mut CEXR_IStream,
+                                               num_threads: c_int,
+                                               out: *mut *mut CEXR_MultiPartInputFile,
+                                               error_out: *mut *const c_char)
+                                               -> c_int;
+    pub fn CEXR_MultiPartInputFile_delete(multi_part_input_file: *mut CEXR_MultiPartInputFile);
+    pub fn CEXR_MultiPartInputFile_parts(multi_part_input_file: *const CEXR_MultiPartInputFile) -> c_int;
+    pub fn CEXR_MultiPartInputFile_header(multi_part_input_file: *const CEXR_MultiPartInputFile,
+                                          part: c_int)
+                                          -> *const CEXR_Header;
+    pub fn CEXR_MultiPartInputFile_set_framebuffer(multi_part_input_file: *mut CEXR_MultiPartInputFile,
+                                                   part: c_int,
+                                                   frame_buffer: *mut CEXR_FrameBuffer,
+                                                   error_out: *mut *const c_char)
+                                                   -> c_int;
+    pub fn CEXR_MultiPartInputFile_read_pixels(multi_part_input_file: *mut CEXR_MultiPartInputFile,
+                                               part: c_int,
+                                               scanline_1: c_int,
+                                               scanline_2: c_int,
+                                               error_out: *mut *const c_char)
+                                               -> c_int;
+}
+
+
+// ------------------------------------------------------------------------------
+// MultiPartOutputFile
+#[repr(C)]
+pub struct CEXR_MultiPartOutputFile {
+    multi_part_output_file: *mut c_void,
+}
+
+extern "C" {
+    pub fn CEXR_MultiPartOutputFile_from_stream(ostream: *mut CEXR_OStream,
+                                                headers: *const *const CEXR_Header,
+                                                num_parts: c_int,
+                                                num_threads: c_int,
+                                                out: *mut *mut CEXR_MultiPartOutputFile,
+                                                error_out: *mut *const c_char)
+                                                -> c_int;
+    pub fn CEXR_MultiPartOutputFile_delete(multi_part_output_file: *mut CEXR_MultiPartOutputFile);
+    pub fn CEXR_MultiPartOutputFile_header(multi_part_output_file: *const CEXR_MultiPartOutputFile,
+                                           part: c_int)
+                                           -> *const CEXR_Header;
+    pub fn CEXR_MultiPartOutputFile_set_framebuffer(multi_part_output_file: *mut CEXR_MultiPartOutputFile,
+                                                    part: c_int,
+                                                    frame_buffer: *const CEXR_FrameBuffer,
+                                                    error_out: *mut *const c_char)
+                                                    -> c_int;
+    pub fn CEXR_MultiPartOutputFile_write_pixels(multi_part_output_file: *mut CEXR_MultiPartOutputFile,
+                                                 part: c_int,
+                                                 num_scanlines: c_int,
+                                                 error_out: *mut *const c_char)
+                                                 -> c_int;
+}
+
 
 #[cfg(test)]
 mod tests {
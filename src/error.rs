@@ -1,40 +1,89 @@
 //! Result and Error types.
 
 use std::ffi::CStr;
+use std::fmt;
+use std::io;
 
 /// Error type for this crate.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
-    /// A generic error, with a description string.
-    Generic(String),
+    /// An I/O error occurred while reading from or writing to the
+    /// underlying stream.
+    Io(io::Error),
+
+    /// A `FrameBuffer`'s dimensions don't match what was expected.
+    DimensionMismatch {
+        /// The dimensions that were expected, in pixels.
+        expected: (u32, u32),
+        /// The dimensions that were actually found, in pixels.
+        got: (u32, u32),
+    },
+
+    /// A channel's `PixelType` or subsampling doesn't match between a
+    /// `Header` and a `FrameBuffer`.
+    ChannelTypeMismatch {
+        /// The name of the mismatched channel.
+        channel: String,
+    },
+
+    /// The requested operation isn't supported for the given input, e.g. a
+    /// resource limit was exceeded or a precondition wasn't met.
+    Unsupported(String),
+
+    /// An error message lifted verbatim from the underlying C++ library.
+    C(String),
+
+    /// A write would have exceeded a configured byte limit.
+    LimitExceeded {
+        /// The configured limit, in bytes.
+        limit: u64,
+        /// The file offset the write would have reached.
+        attempted: u64,
+    },
 }
 
 impl Error {
-    /// Construct an `Error` from a malloc-allocated C string, then free the C string.
+    /// Construct an `Error::C` from a malloc-allocated C string, then free
+    /// the C string.
     pub(crate) fn take(x: *const libc::c_char) -> Self {
         unsafe {
             let msg = CStr::from_ptr(x).to_string_lossy().into_owned();
             libc::free(x as *mut _);
-            Error::Generic(msg)
+            Error::C(msg)
         }
     }
 }
 
-impl std::error::Error for Error {
-    fn description(&self) -> &str {
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use self::Error::*;
         match *self {
-            Generic(ref x) => x,
+            Io(ref e) => write!(f, "I/O error: {}", e),
+            DimensionMismatch { expected, got } => write!(
+                f,
+                "dimension mismatch: expected {}x{}, got {}x{}",
+                expected.0, expected.1, got.0, got.1
+            ),
+            ChannelTypeMismatch { ref channel } => {
+                write!(f, "channel type mismatch for channel '{}'", channel)
+            }
+            Unsupported(ref x) => f.pad(x),
+            C(ref x) => f.pad(x),
+            LimitExceeded { limit, attempted } => write!(
+                f,
+                "write would reach byte {}, exceeding the {}-byte limit",
+                attempted, limit
+            ),
         }
     }
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        use self::Error::*;
-        match *self {
-            Generic(ref x) => f.pad(x),
-        }
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
     }
 }
 
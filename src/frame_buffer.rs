@@ -38,8 +38,58 @@ use openexr_sys::*;
 
 use cexr_type_aliases::*;
 use error::*;
+use header::divide_round_up;
 use Header;
 
+// Shared by the `insert_channel`/`insert_channels` methods of both
+// `FrameBuffer` and `FrameBufferMut`: panics early and clearly if the
+// caller's buffer doesn't have exactly one element per pixel, rather than
+// letting a short buffer turn into undefined behavior once it reaches the
+// FFI `read_pixels`/`write_pixels` calls.
+//
+// This only covers the non-subsampled, full-resolution case that these
+// methods actually construct (they always pass `(1, 1)` sampling to
+// `insert_raw`); `Header::channel_requirements`/`required_elements` is the
+// place to go for the subsampled case when working against a specific
+// channel layout from a file.
+fn check_required_len(len: usize, dimensions: (u32, u32)) {
+    let required = dimensions.0 as usize * dimensions.1 as usize;
+    if len != required {
+        panic!(
+            "data size of {} elements cannot back {}x{} framebuffer (requires {} elements)",
+            len, dimensions.0, dimensions.1, required
+        );
+    }
+}
+
+// Shared by `insert_raw_channel` on both `FrameBuffer` and `FrameBufferMut`:
+// panics early if `data` is too short to back a channel of `dimensions`
+// subsampled by `sampling` and laid out with the given strides, rather than
+// letting it turn into undefined behavior once it reaches the FFI
+// `read_pixels`/`write_pixels` calls.
+fn check_required_raw_len(
+    len: usize,
+    dimensions: (u32, u32),
+    sampling: (u32, u32),
+    element_stride: usize,
+    row_stride: usize,
+    element_size: usize,
+) {
+    let columns = divide_round_up(dimensions.0, sampling.0) as usize;
+    let rows = divide_round_up(dimensions.1, sampling.1) as usize;
+    let required = if columns == 0 || rows == 0 {
+        0
+    } else {
+        row_stride * (rows - 1) + element_stride * (columns - 1) + element_size
+    };
+    if len < required {
+        panic!(
+            "data of {} bytes cannot back a {}x{} channel subsampled by {:?} with \
+             element stride {} and row stride {} (requires {} bytes)",
+            len, dimensions.0, dimensions.1, sampling, element_stride, row_stride, required
+        );
+    }
+}
 
 /// Points to and describes in-memory image data for reading.
 pub struct FrameBuffer<'a> {
@@ -73,12 +123,7 @@ impl<'a> FrameBuffer<'a> {
     /// width * height elements, where width and height are the dimensions
     /// of the `FrameBuffer`.
     pub fn insert_channel<T: PixelData>(&mut self, name: &str, data: &'a [T]) -> &mut Self {
-        if data.len() != self.dimensions.0 as usize * self.dimensions.1 as usize {
-            panic!("data size of {} elements cannot back {}x{} framebuffer",
-                   data.len(),
-                   self.dimensions.0,
-                   self.dimensions.1);
-        }
+        check_required_len(data.len(), self.dimensions);
         let width = self.dimensions.0;
         unsafe {
             self.insert_raw(name,
@@ -102,12 +147,7 @@ impl<'a> FrameBuffer<'a> {
     /// width * height elements, where width and height are the dimensions
     /// of the `FrameBuffer`.
     pub fn insert_channels<T: PixelStruct>(&mut self, names: &[&str], data: &'a [T]) -> &mut Self {
-        if data.len() != self.dimensions.0 as usize * self.dimensions.1 as usize {
-            panic!("data size of {} elements cannot back {}x{} framebuffer",
-                   data.len(),
-                   self.dimensions.0,
-                   self.dimensions.1);
-        }
+        check_required_len(data.len(), self.dimensions);
         let width = self.dimensions.0;
         for (name, (ty, offset)) in names.iter().zip(T::channels()) {
             unsafe {
@@ -123,6 +163,55 @@ impl<'a> FrameBuffer<'a> {
         self
     }
 
+    /// Inserts a single channel with an explicit pixel type, subsampling,
+    /// and memory layout.
+    ///
+    /// Unlike `insert_channel`, `type_` is given explicitly rather than
+    /// inferred from a `PixelData` type parameter, so it can describe a
+    /// channel whose type isn't known until runtime -- for example, a HALF
+    /// color channel next to a FLOAT depth channel, read back with
+    /// `type_`/`x_sampling`/`y_sampling` taken from `Header::channels()`
+    /// rather than assumed.  `data` is the raw bytes backing the channel,
+    /// and `element_stride`/`row_stride` are both in bytes, letting the
+    /// channel's elements be interleaved with other channels' in memory.
+    ///
+    /// Like `insert_channel`/`insert_channels`, this is a checked wrapper
+    /// around `insert_raw`.
+    pub fn insert_raw_channel(
+        &mut self,
+        name: &str,
+        type_: PixelType,
+        data: &'a [u8],
+        element_stride: usize,
+        row_stride: usize,
+        sampling: (u32, u32),
+    ) -> &mut Self {
+        let element_size = match type_ {
+            PixelType::HALF => 2,
+            PixelType::UINT | PixelType::FLOAT => 4,
+        };
+        check_required_raw_len(
+            data.len(),
+            self.dimensions,
+            sampling,
+            element_stride,
+            row_stride,
+            element_size,
+        );
+        unsafe {
+            self.insert_raw(
+                name,
+                type_,
+                data.as_ptr() as *const c_char,
+                (element_stride, row_stride),
+                (sampling.0 as c_int, sampling.1 as c_int),
+                0.0,
+                (false, false),
+            )
+        };
+        self
+    }
+
     /// The raw method for inserting a new channel.
     ///
     /// This is very unsafe: the other methods should be preferred unless you
@@ -179,12 +268,10 @@ impl<'a> FrameBuffer<'a> {
         let w = header.data_window();
         if (w.max.x - w.min.x) as u32 != self.dimensions().0 - 1 ||
            (w.max.y - w.min.y) as u32 != self.dimensions().1 - 1 {
-            return Err(Error::Generic(format!("framebuffer size {}x{} does not \
-                match output file dimensions {}x{}",
-                                              self.dimensions().0,
-                                              self.dimensions().1,
-                                              w.max.x - w.min.x,
-                                              w.max.y - w.min.y)));
+            return Err(Error::DimensionMismatch {
+                expected: ((w.max.x - w.min.x) as u32 + 1, (w.max.y - w.min.y) as u32 + 1),
+                got: self.dimensions(),
+            });
         }
 
         Ok(())
@@ -224,12 +311,7 @@ impl<'a> FrameBufferMut<'a> {
                                         fill: f64,
                                         data: &'a mut [T])
                                         -> &mut Self {
-        if data.len() != self.dimensions.0 as usize * self.dimensions.1 as usize {
-            panic!("data size of {} elements cannot back {}x{} framebuffer",
-                   data.len(),
-                   self.dimensions.0,
-                   self.dimensions.1);
-        }
+        check_required_len(data.len(), self.dimensions);
         let width = self.dimensions.0;
         unsafe {
             self.insert_raw(name,
@@ -258,12 +340,7 @@ impl<'a> FrameBufferMut<'a> {
                                            names_and_fills: &[(&str, f64)],
                                            data: &'a mut [T])
                                            -> &mut Self {
-        if data.len() != self.dimensions.0 as usize * self.dimensions.1 as usize {
-            panic!("data size of {} elements cannot back {}x{} framebuffer",
-                   data.len(),
-                   self.dimensions.0,
-                   self.dimensions.1);
-        }
+        check_required_len(data.len(), self.dimensions);
         let width = self.dimensions.0;
         for (&(name, fill), (ty, offset)) in names_and_fills.iter().zip(T::channels()) {
             unsafe {
@@ -279,6 +356,58 @@ impl<'a> FrameBufferMut<'a> {
         self
     }
 
+    /// Inserts a single channel with an explicit pixel type, subsampling,
+    /// and memory layout.
+    ///
+    /// Unlike `insert_channel`, `type_` is given explicitly rather than
+    /// inferred from a `PixelData` type parameter, so it can describe a
+    /// channel whose type isn't known until runtime -- for example, a HALF
+    /// color channel next to a FLOAT depth channel, read back with
+    /// `type_`/`x_sampling`/`y_sampling` taken from `Header::channels()`
+    /// rather than assumed.  `data` is the raw bytes backing the channel,
+    /// and `element_stride`/`row_stride` are both in bytes, letting the
+    /// channel's elements be interleaved with other channels' in memory.
+    /// `fill` is used for all pixels if a file is read that doesn't have a
+    /// channel with this name.
+    ///
+    /// Like `insert_channel`/`insert_channels`, this is a checked wrapper
+    /// around `insert_raw`.
+    pub fn insert_raw_channel(
+        &mut self,
+        name: &str,
+        type_: PixelType,
+        data: &'a mut [u8],
+        element_stride: usize,
+        row_stride: usize,
+        sampling: (u32, u32),
+        fill: f64,
+    ) -> &mut Self {
+        let element_size = match type_ {
+            PixelType::HALF => 2,
+            PixelType::UINT | PixelType::FLOAT => 4,
+        };
+        check_required_raw_len(
+            data.len(),
+            self.dimensions,
+            sampling,
+            element_stride,
+            row_stride,
+            element_size,
+        );
+        unsafe {
+            self.insert_raw(
+                name,
+                type_,
+                data.as_mut_ptr() as *mut c_char,
+                (element_stride, row_stride),
+                (sampling.0 as c_int, sampling.1 as c_int),
+                fill,
+                (false, false),
+            )
+        };
+        self
+    }
+
     /// The raw method for inserting a new channel.
     ///
     /// This is very unsafe: the other methods should be preferred unless you
@@ -331,6 +460,353 @@ impl<'a> Deref for FrameBufferMut<'a> {
 
 // ----------------------------------------------------------------
 
+/// How pixels outside the data window are filled when reading into a
+/// region larger than it, via `InputFile::read_pixels_sampled`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplingMode {
+    /// Leave out-of-window pixels at each channel's fill value (zero).
+    /// This is the same behavior as plain `read_pixels`.
+    Black,
+    /// Replicate the nearest edge pixel of the data window.
+    Clamp,
+    /// Tile the data window, wrapping coordinates modulo its size.
+    Repeat,
+}
+
+// ----------------------------------------------------------------
+
+/// Owns a buffer for every channel in a `Header`, at each channel's own
+/// native `PixelType` and subsampling.
+///
+/// This is the building block for reading or writing a file without
+/// knowing its channel names or types ahead of time -- AOVs, masks, `Z`,
+/// or any other arbitrary channel list. Round-tripping an unknown file
+/// (read every channel, then write it back out identically) is just:
+///
+/// ```no_run
+/// # use openexr::{InputFile, ScanlineOutputFile, AllChannelsFrameBuffer};
+/// let mut in_file = std::fs::File::open("input_file.exr").unwrap();
+/// let mut input_file = InputFile::new(&mut in_file).unwrap();
+///
+/// let mut buffer = AllChannelsFrameBuffer::new(input_file.header());
+/// input_file.read_pixels(&mut buffer.frame_buffer_mut()).unwrap();
+///
+/// let mut out_file = std::fs::File::create("output_file.exr").unwrap();
+/// let mut output_file =
+///     ScanlineOutputFile::new(&mut out_file, input_file.header()).unwrap();
+/// output_file.write_pixels(&buffer.frame_buffer()).unwrap();
+/// ```
+pub struct AllChannelsFrameBuffer {
+    dimensions: (u32, u32),
+    // name, pixel type, (x, y) subsampling, tightly-packed bytes.
+    channels: Vec<(String, PixelType, (u32, u32), Vec<u8>)>,
+}
+
+impl AllChannelsFrameBuffer {
+    /// Allocates a zero-filled buffer for every channel in `header`, sized
+    /// for `header`'s data window and each channel's own subsampling.
+    pub fn new(header: &Header) -> Self {
+        let (width, height) = header.data_dimensions();
+        Self::new_for_region(header, width, height)
+    }
+
+    /// Like `new()`, but sized for an arbitrary `width` x `height` region
+    /// rather than `header`'s own data window.
+    ///
+    /// Used by `InputFile::read_pixels_sampled()` to allocate a buffer for
+    /// a region that may be larger than the file's data window.
+    pub(crate) fn new_for_region(header: &Header, width: u32, height: u32) -> Self {
+        let mut channels = Vec::new();
+        for chan in header.channels() {
+            // `header.channels()` only fails on a null or non-UTF-8 channel
+            // name; a header with such a channel makes this panic.
+            let (name, channel) = chan.expect("header's own channel list is well-formed");
+            let sampling = (channel.x_sampling as u32, channel.y_sampling as u32);
+            let element_size = match channel.pixel_type {
+                PixelType::HALF => 2,
+                PixelType::UINT | PixelType::FLOAT => 4,
+            };
+            let columns = divide_round_up(width, sampling.0) as usize;
+            let rows = divide_round_up(height, sampling.1) as usize;
+            channels.push((
+                name.to_string(),
+                channel.pixel_type,
+                sampling,
+                vec![0u8; element_size * columns * rows],
+            ));
+        }
+        AllChannelsFrameBuffer {
+            dimensions: (width, height),
+            channels,
+        }
+    }
+
+    /// Returns the channel names, pixel types, and subsampling, alongside
+    /// their raw, tightly-packed byte buffers.
+    pub fn channels(&self) -> impl Iterator<Item = (&str, PixelType, (u32, u32), &[u8])> {
+        self.channels
+            .iter()
+            .map(|&(ref name, ty, sampling, ref data)| (name.as_str(), ty, sampling, data.as_slice()))
+    }
+
+    /// Builds a `FrameBufferMut` pointing at every channel's buffer, ready
+    /// to pass to `InputFile::read_pixels` or similar.
+    pub fn frame_buffer_mut(&mut self) -> FrameBufferMut {
+        let mut fb = FrameBufferMut::new(self.dimensions.0, self.dimensions.1);
+        for &mut (ref name, pixel_type, sampling, ref mut data) in &mut self.channels {
+            let element_size = match pixel_type {
+                PixelType::HALF => 2,
+                PixelType::UINT | PixelType::FLOAT => 4,
+            };
+            let columns = divide_round_up(self.dimensions.0, sampling.0) as usize;
+            fb.insert_raw_channel(
+                name,
+                pixel_type,
+                data,
+                element_size,
+                element_size * columns,
+                sampling,
+                0.0,
+            );
+        }
+        fb
+    }
+
+    /// Builds a `FrameBuffer` pointing at every channel's buffer, ready to
+    /// pass to `ScanlineOutputFile::write_pixels` or similar.
+    pub fn frame_buffer(&self) -> FrameBuffer {
+        let mut fb = FrameBuffer::new(self.dimensions.0, self.dimensions.1);
+        for &(ref name, pixel_type, sampling, ref data) in &self.channels {
+            let element_size = match pixel_type {
+                PixelType::HALF => 2,
+                PixelType::UINT | PixelType::FLOAT => 4,
+            };
+            let columns = divide_round_up(self.dimensions.0, sampling.0) as usize;
+            fb.insert_raw_channel(
+                name,
+                pixel_type,
+                data,
+                element_size,
+                element_size * columns,
+                sampling,
+            );
+        }
+        fb
+    }
+
+    /// Builds a `FrameBufferMut` over the `sub_width` x `sub_height`
+    /// sub-rect of every channel's buffer starting at pixel offset `(x,
+    /// y)`, ready to pass to `InputFile::read_pixels` or similar.
+    ///
+    /// `data_origin` is the absolute origin of the data window being read
+    /// into this sub-rect.  OpenEXR addresses scanlines and columns using
+    /// that absolute coordinate against the framebuffer's base pointer
+    /// (the same `base + y*yStride + x*xStride` addressing used
+    /// throughout this crate), so the slice's base has to be shifted back
+    /// by `data_origin` in addition to `(x, y)` -- using `(x, y)` alone is
+    /// only correct for files whose data window starts at `(0, 0)`. This
+    /// is why we go through `insert_raw` with a manually offset pointer
+    /// rather than `insert_raw_channel`: the shifted base can legitimately
+    /// point before the start of `data` when `data_origin` is positive.
+    ///
+    /// Used by `InputFile::read_pixels_sampled()` to read a file's data
+    /// window directly into its place within a larger buffer.
+    pub(crate) fn frame_buffer_mut_region(
+        &mut self,
+        x: u32,
+        y: u32,
+        sub_width: u32,
+        sub_height: u32,
+        data_origin: (i32, i32),
+    ) -> FrameBufferMut {
+        let mut fb = FrameBufferMut::new(sub_width, sub_height);
+        for &mut (ref name, pixel_type, sampling, ref mut data) in &mut self.channels {
+            let element_size = match pixel_type {
+                PixelType::HALF => 2,
+                PixelType::UINT | PixelType::FLOAT => 4,
+            };
+            let columns = divide_round_up(self.dimensions.0, sampling.0) as usize;
+            let row_stride = element_size * columns;
+            let col_offset = x as isize / sampling.0 as isize;
+            let row_offset = y as isize / sampling.1 as isize;
+            let origin_col = data_origin.0 as isize / sampling.0 as isize;
+            let origin_row = data_origin.1 as isize / sampling.1 as isize;
+            let byte_offset = (row_offset - origin_row) * row_stride as isize +
+                (col_offset - origin_col) * element_size as isize;
+            unsafe {
+                fb.insert_raw(
+                    name,
+                    pixel_type,
+                    (data.as_mut_ptr() as *mut c_char).offset(byte_offset),
+                    (element_size, row_stride),
+                    (sampling.0 as c_int, sampling.1 as c_int),
+                    0.0,
+                    (false, false),
+                );
+            }
+        }
+        fb
+    }
+
+    /// Fills every pixel outside the `width` x `height` sub-rect starting
+    /// at `(x, y)` by sampling that sub-rect's edge according to `mode`,
+    /// rather than leaving it at the channel's fill value.
+    ///
+    /// Used by `InputFile::read_pixels_sampled()` after reading the data
+    /// window into its place within the buffer, to extend it out to the
+    /// rest of a larger requested region.
+    pub(crate) fn extend_edges(&mut self, x: u32, y: u32, width: u32, height: u32, mode: SamplingMode) {
+        for &mut (_, pixel_type, sampling, ref mut data) in &mut self.channels {
+            let element_size = match pixel_type {
+                PixelType::HALF => 2,
+                PixelType::UINT | PixelType::FLOAT => 4,
+            };
+            let columns = divide_round_up(self.dimensions.0, sampling.0) as i64;
+            let rows = divide_round_up(self.dimensions.1, sampling.1) as i64;
+            let row_stride = element_size * columns as usize;
+            let col_off = (x / sampling.0) as i64;
+            let row_off = (y / sampling.1) as i64;
+            let window_cols = divide_round_up(width, sampling.0) as i64;
+            let window_rows = divide_round_up(height, sampling.1) as i64;
+
+            for row in 0..rows {
+                for col in 0..columns {
+                    let in_window = row >= row_off && row < row_off + window_rows && col >= col_off &&
+                        col < col_off + window_cols;
+                    if in_window {
+                        continue;
+                    }
+
+                    let (src_col, src_row) = match mode {
+                        SamplingMode::Black => continue,
+                        SamplingMode::Clamp => (
+                            col_off + (col - col_off).max(0).min(window_cols - 1),
+                            row_off + (row - row_off).max(0).min(window_rows - 1),
+                        ),
+                        SamplingMode::Repeat => (
+                            col_off + (col - col_off).rem_euclid(window_cols),
+                            row_off + (row - row_off).rem_euclid(window_rows),
+                        ),
+                    };
+
+                    let dst_offset = row as usize * row_stride + col as usize * element_size;
+                    let src_offset = src_row as usize * row_stride + src_col as usize * element_size;
+                    if dst_offset != src_offset {
+                        data.copy_within(src_offset..src_offset + element_size, dst_offset);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------
+
+/// Holds per-pixel deep sample data for a set of channels, alongside the
+/// per-pixel sample counts that describe how many samples each pixel has.
+///
+/// Deep images store a variable number of samples per pixel (for things
+/// like volumetric data or order-independent transparency), so unlike
+/// `AllChannelsFrameBuffer` this can't be backed by one fixed-size buffer
+/// per channel -- each pixel of each channel gets its own `Vec<f32>`.
+/// Building one is a two-step process, mirroring
+/// `DeepScanLineInputFile`/`DeepScanLineOutputFile`'s own two-pass API:
+///
+/// 1. `new()` allocates a zeroed sample-count buffer, sized to `header`'s
+///    data window, and one (initially empty) channel per channel in
+///    `header`.
+/// 2. `allocate_samples()` -- after the counts have been filled in, by
+///    `DeepScanLineInputFile::read_sample_counts` when reading, or set by
+///    hand when writing -- allocates each channel's per-pixel sample
+///    buffers to match.
+///
+/// Channels are typically `HALF`/`FLOAT`, with an optional `FLOAT` `Z`/
+/// `ZBack`; like `DeepScanLineInputFile::read_channel`, samples are always
+/// stored and transferred as `f32` regardless of the channel's `PixelType`
+/// in the header.
+pub struct DeepFrameBuffer {
+    dimensions: (u32, u32),
+    sample_counts: Vec<u32>,
+    channels: Vec<(String, Vec<Vec<f32>>)>,
+}
+
+impl DeepFrameBuffer {
+    /// Allocates a buffer for every channel in `header`, sized for
+    /// `header`'s data window, with a zeroed sample-count buffer and no
+    /// samples yet.
+    pub fn new(header: &Header) -> Self {
+        let dimensions = header.data_dimensions();
+        let pixel_count = dimensions.0 as usize * dimensions.1 as usize;
+        let channels = header
+            .channels()
+            .filter_map(|chan| chan.ok())
+            .map(|(name, _)| (name.to_string(), Vec::new()))
+            .collect();
+        DeepFrameBuffer {
+            dimensions,
+            sample_counts: vec![0; pixel_count],
+            channels,
+        }
+    }
+
+    /// Returns the dimensions of the buffer.
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    /// The per-pixel sample counts, in data-window row-major order.
+    pub fn sample_counts(&self) -> &[u32] {
+        &self.sample_counts
+    }
+
+    /// Mutable access to the per-pixel sample counts, e.g. to pass to
+    /// `DeepScanLineInputFile::read_sample_counts`, or to fill in by hand
+    /// before writing.
+    pub fn sample_counts_mut(&mut self) -> &mut [u32] {
+        &mut self.sample_counts
+    }
+
+    /// (Re)allocates every channel's per-pixel sample buffers to match
+    /// `sample_counts()`, zero-filled.
+    ///
+    /// Call this after `sample_counts()` has its final values, and before
+    /// reading or writing any channel's samples.
+    pub fn allocate_samples(&mut self) {
+        for &mut (_, ref mut pixels) in &mut self.channels {
+            *pixels = self
+                .sample_counts
+                .iter()
+                .map(|&count| vec![0.0f32; count as usize])
+                .collect();
+        }
+    }
+
+    /// Returns the channel names in this buffer.
+    pub fn channel_names(&self) -> impl Iterator<Item = &str> {
+        self.channels.iter().map(|&(ref name, _)| name.as_str())
+    }
+
+    /// The per-pixel sample vectors for channel `name`, in data-window
+    /// row-major order, or `None` if `name` isn't one of this buffer's
+    /// channels.
+    pub fn channel(&self, name: &str) -> Option<&[Vec<f32>]> {
+        self.channels
+            .iter()
+            .find(|&&(ref n, _)| n == name)
+            .map(|&(_, ref pixels)| pixels.as_slice())
+    }
+
+    /// Mutable access to the per-pixel sample vectors for channel `name`.
+    pub fn channel_mut(&mut self, name: &str) -> Option<&mut [Vec<f32>]> {
+        self.channels
+            .iter_mut()
+            .find(|&&mut (ref n, _)| n == name)
+            .map(|&mut (_, ref mut pixels)| pixels.as_mut_slice())
+    }
+}
+
+// ----------------------------------------------------------------
+
 /// Types that can be inserted into a `FrameBuffer` as a channel.
 ///
 /// Implementing this trait on a type allows the type to be used directly by the
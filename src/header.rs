@@ -1,6 +1,7 @@
 //! Header and related types.
 
 use std::{self, slice, ptr};
+use std::collections::BTreeSet;
 use std::ffi::{CStr, CString};
 use std::marker::PhantomData;
 
@@ -158,6 +159,10 @@ impl Header {
     }
 
     /// Sets the compression mode.
+    ///
+    /// Takes effect as soon as this header is passed to
+    /// `ScanlineOutputFile::new` or `TiledOutputFile::new` -- there's no
+    /// separate compression setting on the output file types themselves.
     pub fn set_compression(&mut self, compression: Compression) -> &mut Self {
         unsafe {
             CEXR_Header_set_compression(self.handle, compression);
@@ -165,6 +170,107 @@ impl Header {
         self
     }
 
+    /// Returns the current compression mode.
+    pub fn compression(&self) -> Compression {
+        unsafe { CEXR_Header_compression(self.handle) }
+    }
+
+    /// Returns the number of scanlines OpenEXR groups together into a
+    /// single compressed chunk for this header's compression mode.
+    ///
+    /// This is a fixed property of each compression method (1 for
+    /// `ZIPS_COMPRESSION`, 16 for `ZIP_COMPRESSION`, 32 for
+    /// `DWAA_COMPRESSION`, 256 for `DWAB_COMPRESSION`, and 1 for everything
+    /// else), and determines the granularity at which
+    /// `InputFile::read_blocks()` can read and decompress chunks
+    /// independently.
+    pub fn block_scanline_count(&self) -> u32 {
+        match self.compression() {
+            Compression::ZIP_COMPRESSION => 16,
+            Compression::DWAA_COMPRESSION => 32,
+            Compression::DWAB_COMPRESSION => 256,
+            _ => 1,
+        }
+    }
+
+    /// Sets the DWA compression quality level.
+    ///
+    /// This only has an effect when the compression mode is
+    /// `DWAA_COMPRESSION` or `DWAB_COMPRESSION`; for any other compression
+    /// mode the `dwaCompressionLevel` attribute is erased instead, since
+    /// OpenEXR's `DwaCompressor` ignores it otherwise.  If unset, OpenEXR
+    /// defaults to a level of 45.0.
+    ///
+    /// Because this checks the *current* compression mode, call
+    /// `set_compression(DWAA_COMPRESSION)` or `set_compression(DWAB_COMPRESSION)`
+    /// before this, not after -- calling it first is a silent no-op.
+    pub fn set_dwa_compression_level(&mut self, level: f32) -> &mut Self {
+        match self.compression() {
+            Compression::DWAA_COMPRESSION | Compression::DWAB_COMPRESSION => unsafe {
+                CEXR_Header_set_float_attribute(
+                    self.handle,
+                    b"dwaCompressionLevel\0".as_ptr() as *const _,
+                    level,
+                );
+            },
+            _ => unsafe {
+                CEXR_Header_erase_attribute(
+                    self.handle,
+                    b"dwaCompressionLevel\0".as_ptr() as *const _,
+                );
+            },
+        }
+        self
+    }
+
+    /// Returns the DWA compression quality level, if it has been set.
+    pub fn dwa_compression_level(&self) -> Option<f32> {
+        self.get_f32_attribute("dwaCompressionLevel")
+    }
+
+    /// Sets the tile description, making this header describe a tiled
+    /// image.
+    ///
+    /// `x_size`/`y_size` give the dimensions of each tile.  `level_mode`
+    /// and `rounding_mode` control whether (and how) mip/rip-map levels are
+    /// generated -- see `LevelMode` and `RoundingMode` for details.  This
+    /// pairs naturally with `set_envmap()` for authoring tiled environment
+    /// maps.
+    pub fn set_tile_description(
+        &mut self,
+        x_size: u32,
+        y_size: u32,
+        level_mode: LevelMode,
+        rounding_mode: RoundingMode,
+    ) -> &mut Self {
+        unsafe {
+            CEXR_Header_set_tile_description(
+                self.handle,
+                x_size as c_int,
+                y_size as c_int,
+                level_mode as c_int,
+                rounding_mode as c_int,
+            );
+        }
+        self
+    }
+
+    /// Returns whether this header describes a tiled image.
+    pub fn is_tiled(&self) -> bool {
+        unsafe { CEXR_Header_has_tile_description(self.handle) }
+    }
+
+    /// Returns the tile size, if this header describes a tiled image.
+    pub fn tile_size(&self) -> Option<(u32, u32)> {
+        if !self.is_tiled() {
+            return None;
+        }
+        let mut x_size = 0;
+        let mut y_size = 0;
+        unsafe { CEXR_Header_tile_description(self.handle, &mut x_size, &mut y_size, ptr::null_mut(), ptr::null_mut()) };
+        Some((x_size as u32, y_size as u32))
+    }
+
     /// Adds a channel.
     ///
     /// This is a simplified version of `add_channel_detailed()`, using some reasonable
@@ -210,6 +316,58 @@ impl Header {
         unsafe { &*CEXR_Header_data_window(self.handle) }
     }
 
+    /// Computes the exact element and byte count a buffer must have to
+    /// back the channel `name` at this header's resolution, accounting
+    /// for that channel's subsampling.
+    ///
+    /// Returns `None` if there is no channel called `name` in this
+    /// header.  This lets callers size a `Vec` precisely before
+    /// allocating it, rather than computing `width * height` by hand and
+    /// trusting it matches -- a mismatch there is undefined behavior once
+    /// it reaches the FFI `read_pixels`/`write_pixels` calls.
+    pub fn channel_requirements(&self, name: &str) -> Option<(usize, usize)> {
+        let channel = self.get_channel(name)?;
+        let (width, height) = self.data_dimensions();
+        let columns = divide_round_up(width, channel.x_sampling as u32);
+        let rows = divide_round_up(height, channel.y_sampling as u32);
+        let element_count = columns as usize * rows as usize;
+        let bytes_per_element = match channel.pixel_type {
+            PixelType::HALF => 2,
+            PixelType::UINT | PixelType::FLOAT => 4,
+        };
+        Some((element_count, element_count * bytes_per_element))
+    }
+
+    /// Computes the exact element and byte count a single shared buffer
+    /// must have to back all of `names` via `FrameBuffer::insert_channels`
+    /// / `FrameBufferMut::insert_channels`.
+    ///
+    /// All of `names` must share the same element count (they normally do,
+    /// since real-world RGB(A) channels are sampled identically) --
+    /// channels that don't are reported as a `ChannelTypeMismatch` rather
+    /// than silently sizing the buffer for one of them and truncating the
+    /// others.
+    pub fn required_elements(&self, names: &[&str]) -> Result<(usize, usize)> {
+        let mut requirements: Option<(usize, usize)> = None;
+        for &name in names {
+            let reqs = self.channel_requirements(name).ok_or_else(|| {
+                Error::ChannelTypeMismatch {
+                    channel: name.to_string(),
+                }
+            })?;
+            match requirements {
+                None => requirements = Some(reqs),
+                Some((elements, _)) if elements != reqs.0 => {
+                    return Err(Error::ChannelTypeMismatch {
+                        channel: name.to_string(),
+                    });
+                }
+                Some((elements, bytes)) => requirements = Some((elements, bytes + reqs.1)),
+            }
+        }
+        Ok(requirements.unwrap_or((0, 0)))
+    }
+
     /// Access to the display window.
     pub fn display_window(&self) -> &Box2i {
         unsafe { &*CEXR_Header_display_window(self.handle) }
@@ -235,6 +393,129 @@ impl Header {
         }
     }
 
+    /// Returns the set of distinct layer names present in this header's
+    /// channel list.
+    ///
+    /// Real-world EXR files often group channels into named layers using a
+    /// dotted naming convention (e.g. `diffuse.R`, `diffuse.G`,
+    /// `specular.R`).  This builds on `channels()` to enumerate those layer
+    /// names without having to parse channel names by hand.  Channels with
+    /// no `.` in their name belong to the default (unnamed) layer and are
+    /// not included in the returned set.
+    pub fn layers(&self) -> Result<BTreeSet<&str>> {
+        let mut layers = BTreeSet::new();
+        for chan in self.channels() {
+            let (name, _) = chan?;
+            if let Some(layer) = Header::layer_of(name) {
+                layers.insert(layer);
+            }
+        }
+        Ok(layers)
+    }
+
+    /// Returns an iterator over the channels belonging to `layer`.
+    ///
+    /// A channel belongs to `layer` when its name is of the form
+    /// `"<layer>.<rest>"`, following the same dotted naming convention as
+    /// `layers()`.
+    pub fn channels_in_layer<'a>(
+        &'a self,
+        layer: &'a str,
+    ) -> impl Iterator<Item = Result<(&'a str, Channel)>> + 'a {
+        let prefix = format!("{}.", layer);
+        self.channels()
+            .filter(move |chan| match *chan {
+                Ok((name, _)) => name.starts_with(&prefix),
+                Err(_) => true,
+            })
+    }
+
+    /// Returns whether this header describes a multi-layer EXR file.
+    ///
+    /// This is true when there is more than one named layer, or when there
+    /// is a mix of named and unnamed (default layer) channels -- this
+    /// mirrors how DCC tools decide whether a file is a multi-layer render
+    /// pass EXR.
+    pub fn is_multilayer(&self) -> Result<bool> {
+        let mut layers = BTreeSet::new();
+        let mut has_unnamed = false;
+        for chan in self.channels() {
+            let (name, _) = chan?;
+            match Header::layer_of(name) {
+                Some(layer) => {
+                    layers.insert(layer);
+                }
+                None => has_unnamed = true,
+            }
+        }
+        Ok(layers.len() > 1 || (!layers.is_empty() && has_unnamed))
+    }
+
+    /// Sanity-checks the header's dimensions, channel count, and implied
+    /// framebuffer size against `limits`, without trusting anything it
+    /// reports about itself beyond that.
+    ///
+    /// Intended for use before allocating buffers sized from header data
+    /// when reading files from untrusted sources: a malformed or
+    /// adversarial header can report a resolution or channel count that
+    /// would otherwise lead to an enormous or overflowing allocation before
+    /// OpenEXR itself gets a chance to reject the file.
+    pub(crate) fn validate_untrusted_bounds(&self, limits: &ReadLimits) -> Result<()> {
+        const MAX_DIMENSION: i64 = 1 << 16;
+
+        let (width, height) = self.data_dimensions();
+        if width as i64 > MAX_DIMENSION || height as i64 > MAX_DIMENSION {
+            return Err(Error::Unsupported(format!(
+                "data window dimensions {}x{} exceed the sanity limit of {m}x{m}",
+                width, height, m = MAX_DIMENSION
+            )));
+        }
+        let pixel_count = (width as i64) * (height as i64);
+        if pixel_count > limits.max_pixels {
+            return Err(Error::Unsupported(format!(
+                "data window of {}x{} pixels exceeds the configured limit of {} pixels",
+                width, height, limits.max_pixels
+            )));
+        }
+
+        let mut channel_count = 0usize;
+        let mut bytes_per_pixel = 0i64;
+        for chan in self.channels() {
+            let (_, channel) = chan?;
+            channel_count += 1;
+            if channel_count > limits.max_channels {
+                return Err(Error::Unsupported(format!(
+                    "channel list exceeds the configured limit of {} channels",
+                    limits.max_channels
+                )));
+            }
+            bytes_per_pixel += match channel.pixel_type {
+                PixelType::HALF => 2,
+                PixelType::UINT | PixelType::FLOAT => 4,
+            };
+        }
+
+        let total_bytes = pixel_count.saturating_mul(bytes_per_pixel);
+        if total_bytes > limits.max_bytes {
+            return Err(Error::Unsupported(format!(
+                "framebuffer of {} bytes implied by the header exceeds the \
+                 configured limit of {} bytes",
+                total_bytes, limits.max_bytes
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the layer-name prefix of a channel name, or `None` if the
+    /// channel belongs to the default (unnamed) layer.
+    fn layer_of(name: &str) -> Option<&str> {
+        match name.rfind('.') {
+            Some(0) | None => None,
+            Some(i) => Some(&name[..i]),
+        }
+    }
+
     /// Determine whether this header describes an environment map, and if so, what type
     pub fn envmap(&self) -> Option<Envmap> {
         if unsafe { CEXR_Header_has_envmap(self.handle) } {
@@ -258,6 +539,45 @@ impl Header {
         self
     }
 
+    /// Returns the view names declared in the header's `multiView`
+    /// attribute, or an empty iterator if there is no such attribute.
+    ///
+    /// This is a convenience over `multiview()` for callers who don't need
+    /// to distinguish "no multiview attribute" from "an empty one".
+    pub fn views(&self) -> impl Iterator<Item = &str> {
+        self.multiview().into_iter().flatten()
+    }
+
+    /// Returns the full (prefixed) channel names belonging to `layer`.
+    ///
+    /// This is a convenience over `channels_in_layer()` for building a
+    /// `FrameBuffer` for a single layer: the returned names can be passed
+    /// directly to `FrameBuffer::insert_channels()`/
+    /// `FrameBufferMut::insert_channels()`.
+    pub fn layer_channel_names<'a>(&'a self, layer: &'a str) -> Result<Vec<&'a str>> {
+        let mut names = Vec::new();
+        for chan in self.channels_in_layer(layer) {
+            let (name, _) = chan?;
+            names.push(name);
+        }
+        Ok(names)
+    }
+
+    /// Returns the full (prefixed) channel names belonging to `view_name`.
+    ///
+    /// This is a convenience over `channels_in_view()` for building a
+    /// `FrameBuffer` for a single view: the returned names can be passed
+    /// directly to `FrameBuffer::insert_channels()`/
+    /// `FrameBufferMut::insert_channels()`.
+    pub fn view_channel_names<'a>(&'a self, view_name: &'a str) -> Result<Vec<&'a str>> {
+        let mut names = Vec::new();
+        for chan in self.channels_in_view(view_name) {
+            let (name, _) = chan?;
+            names.push(name);
+        }
+        Ok(names)
+    }
+
     /// Access the list of view names, if any
     pub fn multiview(&self) -> Option<impl Iterator<Item=&str>> {
         if !unsafe { CEXR_Header_has_multiview(self.handle) } {
@@ -286,17 +606,152 @@ impl Header {
         self
     }
 
+    /// Sets a custom string attribute.
+    pub fn set_string_attribute(&mut self, name: &str, value: &str) -> &mut Self {
+        let c_name = CString::new(name).unwrap();
+        let c_value = CString::new(value).unwrap();
+        unsafe { CEXR_Header_set_string_attribute(self.handle, c_name.as_ptr(), c_value.as_ptr()) };
+        self
+    }
+
+    /// Gets a custom string attribute, if it exists and is of string type.
+    pub fn get_string_attribute(&self, name: &str) -> Option<String> {
+        let c_name = CString::new(name).unwrap();
+        let value = unsafe { CEXR_Header_get_string_attribute(self.handle, c_name.as_ptr()) };
+        if value.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(value) }.to_string_lossy().into_owned())
+        }
+    }
+
+    /// Sets a custom `f32` attribute.
+    pub fn set_f32_attribute(&mut self, name: &str, value: f32) -> &mut Self {
+        let c_name = CString::new(name).unwrap();
+        unsafe { CEXR_Header_set_float_attribute(self.handle, c_name.as_ptr(), value) };
+        self
+    }
+
+    /// Gets a custom `f32` attribute, if it exists and is of float type.
+    pub fn get_f32_attribute(&self, name: &str) -> Option<f32> {
+        let c_name = CString::new(name).unwrap();
+        let mut value = 0.0f32;
+        if unsafe { CEXR_Header_get_float_attribute(self.handle, c_name.as_ptr(), &mut value) } {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Sets a custom `i32` attribute.
+    pub fn set_i32_attribute(&mut self, name: &str, value: i32) -> &mut Self {
+        let c_name = CString::new(name).unwrap();
+        unsafe { CEXR_Header_set_int_attribute(self.handle, c_name.as_ptr(), value) };
+        self
+    }
+
+    /// Gets a custom `i32` attribute, if it exists and is of int type.
+    pub fn get_i32_attribute(&self, name: &str) -> Option<i32> {
+        let c_name = CString::new(name).unwrap();
+        let mut value = 0i32;
+        if unsafe { CEXR_Header_get_int_attribute(self.handle, c_name.as_ptr(), &mut value) } {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Sets a custom `Box2i` attribute.
+    pub fn set_box2i_attribute(&mut self, name: &str, value: Box2i) -> &mut Self {
+        let c_name = CString::new(name).unwrap();
+        unsafe { CEXR_Header_set_box2i_attribute(self.handle, c_name.as_ptr(), value) };
+        self
+    }
+
+    /// Gets a custom `Box2i` attribute, if it exists and is of box2i type.
+    pub fn get_box2i_attribute(&self, name: &str) -> Option<Box2i> {
+        let c_name = CString::new(name).unwrap();
+        let mut value = Box2i {
+            min: CEXR_V2i { x: 0, y: 0 },
+            max: CEXR_V2i { x: 0, y: 0 },
+        };
+        if unsafe { CEXR_Header_get_box2i_attribute(self.handle, c_name.as_ptr(), &mut value) } {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Sets a custom `V2f` attribute.
+    pub fn set_v2f_attribute(&mut self, name: &str, value: (f32, f32)) -> &mut Self {
+        let c_name = CString::new(name).unwrap();
+        let v = CEXR_V2f { x: value.0, y: value.1 };
+        unsafe { CEXR_Header_set_v2f_attribute(self.handle, c_name.as_ptr(), v) };
+        self
+    }
+
+    /// Gets a custom `V2f` attribute, if it exists and is of v2f type.
+    pub fn get_v2f_attribute(&self, name: &str) -> Option<(f32, f32)> {
+        let c_name = CString::new(name).unwrap();
+        let mut value = CEXR_V2f { x: 0.0, y: 0.0 };
+        if unsafe { CEXR_Header_get_v2f_attribute(self.handle, c_name.as_ptr(), &mut value) } {
+            Some((value.x, value.y))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over all of the header's attributes, yielding
+    /// `(name, type_name)` pairs.
+    ///
+    /// This includes both the well-known attributes exposed elsewhere on
+    /// `Header` (e.g. `envmap`, `multiview`) and any custom attributes set
+    /// via the `set_*_attribute` methods, letting callers discover unknown
+    /// attributes they don't have dedicated accessors for.
+    pub fn attributes(&self) -> AttributeIter {
+        AttributeIter {
+            iterator: unsafe { CEXR_Header_attribute_iter(self.handle) },
+            _phantom_1: PhantomData,
+            _phantom_2: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the channels belonging to `view_name`.
+    ///
+    /// Follows OpenEXR's multiview naming convention: a channel belongs to
+    /// view `V` if one of the dot-separated components of its name equals
+    /// `V`, or if it has no component matching any entry of `multiview()`
+    /// and `V` is the default (first) view in that list.  This lets callers
+    /// split a stereo EXR into left/right framebuffers without hand-coding
+    /// the string matching themselves.
+    pub fn channels_in_view<'a>(
+        &'a self,
+        view_name: &'a str,
+    ) -> impl Iterator<Item = Result<(&'a str, Channel)>> + 'a {
+        let views: BTreeSet<&str> = self.multiview().map_or_else(BTreeSet::new, |v| v.collect());
+        let is_default = self.multiview().and_then(|mut v| v.next()) == Some(view_name);
+
+        self.channels().filter(move |chan| match *chan {
+            Ok((name, _)) => {
+                let mut tagged = name.split('.').filter(|part| views.contains(part));
+                match tagged.next() {
+                    Some(view) => view == view_name,
+                    None => is_default,
+                }
+            }
+            Err(_) => true,
+        })
+    }
+
     pub(crate) fn validate_framebuffer_for_output(&self, framebuffer: &FrameBuffer) -> Result<()> {
         for chan in self.channels() {
             let (name, h_channel) = chan?;
             if let Some(fb_channel) = framebuffer._get_channel(name) {
                 Header::validate_channel(name, &h_channel, &fb_channel)?;
             } else {
-                return Err(Error::Generic(format!(
-                    "FrameBuffer is missing \
-                     channel '{}' expected by Header",
-                    name
-                )));
+                return Err(Error::ChannelTypeMismatch {
+                    channel: name.to_string(),
+                });
             }
         }
         Ok(())
@@ -329,26 +784,29 @@ impl Header {
     // Factored out shared code from the validate_framebuffer_* methods above.
     fn validate_channel(name: &str, h_chan: &Channel, fb_chan: &Channel) -> Result<()> {
         if fb_chan.pixel_type != h_chan.pixel_type {
-            return Err(Error::Generic(format!(
-                "Header and FrameBuffer channel \
-                 types don't match: '{}' is {:?} in Header and {:?} in \
-                 FrameBuffer",
-                name, h_chan.pixel_type, fb_chan.pixel_type
-            )));
+            return Err(Error::ChannelTypeMismatch {
+                channel: name.to_string(),
+            });
         }
         if fb_chan.x_sampling != h_chan.x_sampling || fb_chan.y_sampling != h_chan.y_sampling {
-            return Err(Error::Generic(format!(
-                "Header and FrameBuffer channel \
-                 subsampling don't match: channel '{}' is {}x{} in Header and \
-                 {}x{} in FrameBuffer",
-                name, h_chan.x_sampling, h_chan.y_sampling, fb_chan.x_sampling, fb_chan.y_sampling
-            )));
+            return Err(Error::ChannelTypeMismatch {
+                channel: name.to_string(),
+            });
         }
 
         Ok(())
     }
 }
 
+// Ceiling-divides `value` by `divisor`, matching how OpenEXR computes the
+// resolution of a subsampled channel: `ceil(full_resolution / sampling)`.
+//
+// `pub(crate)` so `frame_buffer`'s raw-channel insertion can size a
+// subsampled buffer the same way without duplicating the formula.
+pub(crate) fn divide_round_up(value: u32, divisor: u32) -> u32 {
+    (value + divisor - 1) / divisor
+}
+
 impl Default for Header {
     fn default() -> Header {
         Header::new()
@@ -381,9 +839,18 @@ impl<'a> Drop for ChannelIter<'a> {
 impl<'a> Iterator for ChannelIter<'a> {
     type Item = Result<(&'a str, Channel)>;
     fn next(&mut self) -> Option<Result<(&'a str, Channel)>> {
-        let mut name = unsafe { std::mem::uninitialized() };
-        let mut channel = unsafe { std::mem::uninitialized() };
+        // Zero-initialized rather than `mem::uninitialized()`: if the C++
+        // side ever fails to populate these out-params on a malformed or
+        // adversarial file, we want a null pointer and a zeroed `Channel`
+        // rather than reading uninitialized memory.
+        let mut name: *const std::os::raw::c_char = ptr::null();
+        let mut channel: Channel = unsafe { std::mem::zeroed() };
         if unsafe { CEXR_ChannelListIter_next(self.iterator, &mut name, &mut channel) } {
+            if name.is_null() {
+                return Some(Err(Error::Unsupported(
+                    "Channel iterator returned a null name pointer".to_string(),
+                )));
+            }
             // TODO: use CStr::from_bytes_with_nul() instead to avoid memory unsafety
             // if the string is not nul terminated.
             let cname = unsafe { CStr::from_ptr(name) };
@@ -391,7 +858,7 @@ impl<'a> Iterator for ChannelIter<'a> {
             if let Ok(n) = str_name {
                 Some(Ok((n, channel)))
             } else {
-                Some(Err(Error::Generic(format!(
+                Some(Err(Error::Unsupported(format!(
                     "Invalid channel name: {:?}",
                     cname
                 ))))
@@ -402,6 +869,48 @@ impl<'a> Iterator for ChannelIter<'a> {
     }
 }
 
+/// An iterator over the attributes in a `Header`.
+///
+/// Yields a tuple of the name and type name (e.g. `"string"`, `"float"`,
+/// `"box2i"`) of each attribute.
+pub struct AttributeIter<'a> {
+    iterator: *mut CEXR_AttributeIter,
+    _phantom_1: PhantomData<CEXR_AttributeIter>,
+    _phantom_2: PhantomData<&'a Header>,
+}
+
+impl<'a> Drop for AttributeIter<'a> {
+    fn drop(&mut self) {
+        unsafe { CEXR_AttributeIter_delete(self.iterator) };
+    }
+}
+
+impl<'a> Iterator for AttributeIter<'a> {
+    type Item = Result<(&'a str, &'a str)>;
+    fn next(&mut self) -> Option<Result<(&'a str, &'a str)>> {
+        let mut name: *const std::os::raw::c_char = ptr::null();
+        let mut type_name: *const std::os::raw::c_char = ptr::null();
+        if unsafe { CEXR_AttributeIter_next(self.iterator, &mut name, &mut type_name) } {
+            if name.is_null() || type_name.is_null() {
+                return Some(Err(Error::Unsupported(
+                    "Attribute iterator returned a null pointer".to_string(),
+                )));
+            }
+            let cname = unsafe { CStr::from_ptr(name) };
+            let ctype = unsafe { CStr::from_ptr(type_name) };
+            match (cname.to_str(), ctype.to_str()) {
+                (Ok(n), Ok(t)) => Some(Ok((n, t))),
+                _ => Some(Err(Error::Unsupported(format!(
+                    "Invalid attribute name or type: {:?}, {:?}",
+                    cname, ctype
+                )))),
+            }
+        } else {
+            None
+        }
+    }
+}
+
 /// Types of environment maps
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Envmap {
@@ -410,3 +919,106 @@ pub enum Envmap {
     /// Cubemap
     Cube = 1,
 }
+
+/// Determines how many levels a tiled image has, and how they relate to
+/// one another.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LevelMode {
+    /// A plain tiled image with a single level.
+    OneLevel = 0,
+    /// A mipmap: one level per resolution, shrinking on both axes together.
+    MipmapLevels = 1,
+    /// A ripmap: one level per combination of x and y resolution.
+    RipmapLevels = 2,
+}
+
+/// Determines how the resolution of successive mip/rip-map levels is
+/// rounded when it isn't an exact power of two.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RoundingMode {
+    /// Round the resolution of each level down.
+    RoundDown = 0,
+    /// Round the resolution of each level up.
+    RoundUp = 1,
+}
+
+/// Resource limits enforced against a header before any pixel data is
+/// allocated or decoded, used by `InputFile::from_untrusted_slice` and
+/// friends to guard against maliciously crafted files.
+///
+/// A malicious (or merely corrupt) header can report an enormous
+/// resolution or channel count, causing huge allocations before OpenEXR
+/// itself ever gets a chance to reject the file. `ReadLimits` lets callers
+/// cap the implied pixel count, the implied framebuffer size in bytes, and
+/// the number of channels, and raise or disable those caps for input that
+/// is already known to be trustworthy.
+///
+/// Note: see `max_compression_ratio`'s own doc comment for a caveat about
+/// what it does and doesn't currently guard against.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ReadLimits {
+    max_pixels: i64,
+    max_bytes: i64,
+    max_channels: usize,
+    max_compression_ratio: f64,
+}
+
+impl ReadLimits {
+    /// Disables all limits, for use with input that is fully trusted.
+    pub fn unlimited() -> ReadLimits {
+        ReadLimits {
+            max_pixels: i64::max_value(),
+            max_bytes: i64::max_value(),
+            max_channels: usize::max_value(),
+            max_compression_ratio: std::f64::INFINITY,
+        }
+    }
+
+    /// Sets the maximum number of pixels (`width * height`) a data window
+    /// may contain.
+    pub fn max_pixels(mut self, max_pixels: i64) -> ReadLimits {
+        self.max_pixels = max_pixels;
+        self
+    }
+
+    /// Sets the maximum size, in bytes, of the framebuffer implied by the
+    /// header's resolution and channel list.
+    pub fn max_bytes(mut self, max_bytes: i64) -> ReadLimits {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Sets the maximum number of channels a header's channel list may
+    /// contain.
+    pub fn max_channels(mut self, max_channels: usize) -> ReadLimits {
+        self.max_channels = max_channels;
+        self
+    }
+
+    /// Sets the maximum ratio of decompressed to compressed size allowed
+    /// for a single scanline block.
+    ///
+    /// This is meant as a guard against decompression-bomb files, but is
+    /// currently accepted and stored without being enforced -- doing so
+    /// requires the per-chunk compressed size, which isn't exposed through
+    /// this crate's C bindings yet. Don't rely on this to bound memory or
+    /// CPU use against untrusted input; use `max_pixels` and `max_bytes`
+    /// for that instead.
+    pub fn max_compression_ratio(mut self, max_compression_ratio: f64) -> ReadLimits {
+        self.max_compression_ratio = max_compression_ratio;
+        self
+    }
+}
+
+impl Default for ReadLimits {
+    /// Generous but finite defaults: up to 2^28 pixels (e.g. a 16k x 16k
+    /// image), a 2 GiB implied framebuffer, and 1024 channels.
+    fn default() -> ReadLimits {
+        ReadLimits {
+            max_pixels: 1 << 28,
+            max_bytes: 1 << 31,
+            max_channels: 1024,
+            max_compression_ratio: 1_000.0,
+        }
+    }
+}
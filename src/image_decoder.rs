@@ -0,0 +1,83 @@
+//! An `image::ImageDecoder` adapter over `InputFile`, enabled via the
+//! `image` feature.
+//!
+//! This lets an EXR file be loaded through `image`'s generic decoding APIs
+//! (`image::load`, format auto-detection, etc.) alongside its other
+//! built-in formats.
+
+use std::io::{Read, Seek};
+
+use image::{ColorType, ImageDecoder, ImageError, ImageResult};
+
+use error::Error;
+use input::InputFile;
+use rgba::rgba_frame_buffer_mut;
+
+/// Wraps an `InputFile` so it can be used anywhere an `image::ImageDecoder`
+/// is expected.
+///
+/// Pixel data is always reported as `ColorType::Rgba32F`: the underlying
+/// `R`/`G`/`B`/`A` channels may be stored as `HALF`, `FLOAT`, or `UINT`, but
+/// `read_image` converts all of them to `f32` the same way `read_rgba`
+/// does, defaulting missing color channels to `0.0` and a missing alpha
+/// channel to `1.0`.
+pub struct ExrDecoder<'a> {
+    input: InputFile<'a>,
+}
+
+impl<'a> ExrDecoder<'a> {
+    /// Creates a new `ExrDecoder` by opening an EXR file from `reader`.
+    pub fn new<T: Read + Seek + 'a>(reader: &mut T) -> Result<Self, Error> {
+        Ok(ExrDecoder {
+            input: InputFile::new(reader)?,
+        })
+    }
+}
+
+impl<'a> ImageDecoder<'a> for ExrDecoder<'a> {
+    fn dimensions(&self) -> (u32, u32) {
+        self.input.header().data_dimensions()
+    }
+
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgba32F
+    }
+
+    fn read_image(mut self, buf: &mut [u8]) -> ImageResult<()>
+    where
+        Self: Sized,
+    {
+        let (width, height) = self.dimensions();
+        let pixel_count = width as usize * height as usize;
+        let origin = self.input.header().data_origin();
+
+        let mut pixels = vec![(0.0f32, 0.0f32, 0.0f32, 1.0f32); pixel_count];
+        {
+            let mut fb = rgba_frame_buffer_mut(&mut pixels, width, height, origin);
+            self.input
+                .read_pixels(&mut fb)
+                .map_err(to_image_error)?;
+        }
+
+        let mut out = buf.chunks_exact_mut(4 * 4);
+        for &(r, g, b, a) in &pixels {
+            let chunk = out.next().expect(
+                "buf is sized by the caller from dimensions()/color_type(), so it must \
+                 have one 16-byte RGBA32F chunk per pixel",
+            );
+            chunk[0..4].copy_from_slice(&r.to_ne_bytes());
+            chunk[4..8].copy_from_slice(&g.to_ne_bytes());
+            chunk[8..12].copy_from_slice(&b.to_ne_bytes());
+            chunk[12..16].copy_from_slice(&a.to_ne_bytes());
+        }
+
+        Ok(())
+    }
+}
+
+fn to_image_error(e: Error) -> ImageError {
+    ImageError::IoError(::std::io::Error::new(
+        ::std::io::ErrorKind::Other,
+        e.to_string(),
+    ))
+}
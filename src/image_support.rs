@@ -0,0 +1,77 @@
+//! Conversion helpers for the [`image`](https://crates.io/crates/image)
+//! crate, enabled via the `image` feature.
+//!
+//! These let downstream code round-trip an RGB(A) EXR file through a plain
+//! `image::DynamicImage` without writing `FrameBuffer` plumbing by hand.
+
+use std::io::{Read, Seek, Write};
+
+use image::{DynamicImage, Rgba32FImage};
+
+use error::{Error, Result};
+use frame_buffer::FrameBuffer;
+use header::Header;
+use input::InputFile;
+use output::ScanlineOutputFile;
+use rgba::rgba_frame_buffer_mut;
+use PixelType;
+
+/// Decodes an RGB(A) EXR file into an owned, floating point
+/// `DynamicImage::ImageRgba32F`.
+///
+/// Channels are read by name (`R`, `G`, `B`, `A`); any of them that are
+/// missing from the file are filled in -- `0.0` for the color channels,
+/// `1.0` for alpha -- rather
+/// than causing an error, since plenty of EXR files are RGB-only.
+pub fn read_rgba<T: Read + Seek>(reader: &mut T) -> Result<DynamicImage> {
+    let mut input = InputFile::new(reader)?;
+    let (width, height) = input.header().data_dimensions();
+    let origin = input.header().data_origin();
+
+    let mut pixels = vec![(0.0f32, 0.0f32, 0.0f32, 1.0f32); width as usize * height as usize];
+    {
+        let mut fb = rgba_frame_buffer_mut(&mut pixels, width, height, origin);
+        input.read_pixels(&mut fb)?;
+    }
+
+    let mut image = Rgba32FImage::new(width, height);
+    for (i, &(r, g, b, a)) in pixels.iter().enumerate() {
+        image.put_pixel(
+            i as u32 % width,
+            i as u32 / width,
+            image::Rgba([r, g, b, a]),
+        );
+    }
+    Ok(DynamicImage::ImageRgba32F(image))
+}
+
+/// Encodes `image` as a floating point RGBA EXR file.
+///
+/// `image` is converted to `f32` RGBA (via `DynamicImage::to_rgba32f`)
+/// before being written; this matches the precision OpenEXR is designed
+/// for, regardless of the source image's original pixel type.
+pub fn write_rgba<T: Write + Seek>(writer: &mut T, image: &DynamicImage) -> Result<()> {
+    let rgba = image.to_rgba32f();
+    let (width, height) = rgba.dimensions();
+    if width == 0 || height == 0 {
+        return Err(Error::Unsupported(
+            "cannot write a zero-sized image to an EXR file".to_string(),
+        ));
+    }
+
+    let pixels: Vec<(f32, f32, f32, f32)> =
+        rgba.pixels().map(|p| (p[0], p[1], p[2], p[3])).collect();
+
+    let mut header = Header::new();
+    header
+        .set_resolution(width, height)
+        .add_channel("R", PixelType::FLOAT)
+        .add_channel("G", PixelType::FLOAT)
+        .add_channel("B", PixelType::FLOAT)
+        .add_channel("A", PixelType::FLOAT);
+
+    let mut output = ScanlineOutputFile::new(writer, &header)?;
+    let mut fb = FrameBuffer::new(width, height);
+    fb.insert_channels(&["R", "G", "B", "A"], &pixels);
+    output.write_pixels(&fb)
+}
@@ -0,0 +1,226 @@
+use std::ffi::{CStr, CString};
+use std::io::{Read, Seek};
+use std::marker::PhantomData;
+use std::ptr;
+
+use libc::{c_float, c_int};
+
+use openexr_sys::*;
+
+use error::*;
+use frame_buffer::DeepFrameBuffer;
+use stream_io::{read_stream, seek_stream};
+use Header;
+
+/// Reads deep scanline OpenEXR files.
+///
+/// Deep images store a variable number of samples per pixel (for things
+/// like volumetric data or order-independent transparency), so unlike
+/// `InputFile`/`TiledInputFile` there's no single `FrameBuffer` shape that
+/// can describe them.  Reading one is a two-step process:
+///
+/// 1. `read_sample_counts()` fills a `width * height` buffer with the
+///    number of samples stored at each pixel.
+/// 2. `read_channel()` reads one channel's samples for every pixel into a
+///    flat buffer, using those same counts to know where each pixel's
+///    samples start and end.
+pub struct DeepScanLineInputFile<'a> {
+    handle: *mut CEXR_DeepScanLineInputFile,
+    header_ref: Header,
+    istream: *mut CEXR_IStream,
+    _phantom_1: PhantomData<CEXR_DeepScanLineInputFile>,
+    _phantom_2: PhantomData<&'a mut ()>, // Represents the borrowed reader
+
+    // NOTE: Because we don't know what type the reader might be, it's important
+    // that this struct remains neither Sync nor Send.  Please don't implement
+    // them!
+}
+
+impl<'a> DeepScanLineInputFile<'a> {
+    /// Creates a new `DeepScanLineInputFile` from any `Read + Seek` type
+    /// (typically a `std::fs::File`).
+    ///
+    /// Note: this seeks to byte 0 before reading.
+    pub fn new<T: 'a>(reader: &mut T) -> Result<DeepScanLineInputFile>
+    where
+        T: Read + Seek,
+    {
+        let istream_ptr = {
+            let read_ptr = read_stream::<T>;
+            let seekp_ptr = seek_stream::<T>;
+
+            let mut error_out = ptr::null();
+            let mut out = ptr::null_mut();
+            let error = unsafe {
+                CEXR_IStream_from_reader(
+                    reader as *mut T as *mut _,
+                    Some(read_ptr),
+                    Some(seekp_ptr),
+                    &mut out,
+                    &mut error_out,
+                )
+            };
+
+            if error != 0 {
+                let msg = unsafe { CStr::from_ptr(error_out) };
+                return Err(Error::C(msg.to_string_lossy().into_owned()));
+            } else {
+                out
+            }
+        };
+
+        let mut error_out = ptr::null();
+        let mut out = ptr::null_mut();
+        let error = unsafe {
+            CEXR_DeepScanLineInputFile_from_stream(istream_ptr, 1, &mut out, &mut error_out)
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(DeepScanLineInputFile {
+                handle: out,
+                header_ref: Header {
+                    // NOTE: We're casting to *mut here to satisfy the
+                    // field's type, but importantly we only return a
+                    // const & of the Header so it retains const semantics.
+                    handle: unsafe { CEXR_DeepScanLineInputFile_header(out) } as *mut CEXR_Header,
+                    owned: false,
+                    _phantom: PhantomData,
+                },
+                istream: istream_ptr,
+                _phantom_1: PhantomData,
+                _phantom_2: PhantomData,
+            })
+        }
+    }
+
+    /// Access to the file's header.
+    pub fn header(&self) -> &Header {
+        &self.header_ref
+    }
+
+    /// Fills `counts` with the number of samples stored at each pixel.
+    ///
+    /// `counts` must have exactly `width * height` elements, in data-window
+    /// row-major order (the same order `InputFile::read_pixels` uses).
+    pub fn read_sample_counts(&mut self, counts: &mut [u32]) -> Result<()> {
+        let (width, height) = self.header().data_dimensions();
+        let required = width as usize * height as usize;
+        if counts.len() != required {
+            return Err(Error::Unsupported(format!(
+                "sample count buffer has {} elements, but the {}x{} data window requires {}",
+                counts.len(),
+                width,
+                height,
+                required
+            )));
+        }
+
+        let mut error_out = ptr::null();
+        let error = unsafe {
+            CEXR_DeepScanLineInputFile_read_pixel_sample_counts(
+                self.handle,
+                counts.as_mut_ptr() as *mut c_int,
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reads channel `name`'s deep samples for every pixel into `samples`.
+    ///
+    /// `counts` must be the buffer previously filled by
+    /// `read_sample_counts()`; `samples` must have exactly
+    /// `counts.iter().sum()` elements.  On return, the samples for the
+    /// pixel at index `i` (in the same row-major order as `counts`) occupy
+    /// `samples[offset..offset + counts[i] as usize]`, where `offset` is
+    /// the sum of all preceding counts.
+    pub fn read_channel(&mut self, name: &str, counts: &[u32], samples: &mut [f32]) -> Result<()> {
+        let (width, height) = self.header().data_dimensions();
+        if counts.len() != width as usize * height as usize {
+            return Err(Error::Unsupported(format!(
+                "sample count buffer has {} elements, but the {}x{} data window requires {}",
+                counts.len(),
+                width,
+                height,
+                width as usize * height as usize
+            )));
+        }
+
+        let total: usize = counts.iter().map(|&c| c as usize).sum();
+        if samples.len() != total {
+            return Err(Error::Unsupported(format!(
+                "sample buffer has {} elements, but the counts sum to {}",
+                samples.len(),
+                total
+            )));
+        }
+
+        let mut sample_pointers: Vec<*mut c_float> = Vec::with_capacity(counts.len());
+        let mut offset = 0usize;
+        for &count in counts {
+            sample_pointers.push(unsafe { samples.as_mut_ptr().add(offset) });
+            offset += count as usize;
+        }
+
+        let c_name = CString::new(name).unwrap();
+        let mut error_out = ptr::null();
+        let error = unsafe {
+            CEXR_DeepScanLineInputFile_read_channel(
+                self.handle,
+                c_name.as_ptr(),
+                sample_pointers.as_mut_ptr(),
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(())
+        }
+    }
+    /// Reads every channel of `framebuffer`.
+    ///
+    /// This is `read_sample_counts()` and `read_channel()` rolled into one
+    /// call, the two-pass read described on `DeepFrameBuffer`: it fills
+    /// `framebuffer`'s sample counts, allocates its per-channel sample
+    /// buffers to match, then reads every one of `framebuffer`'s channels.
+    pub fn read_pixels(&mut self, framebuffer: &mut DeepFrameBuffer) -> Result<()> {
+        self.read_sample_counts(framebuffer.sample_counts_mut())?;
+        framebuffer.allocate_samples();
+
+        let counts = framebuffer.sample_counts().to_vec();
+        let total: usize = counts.iter().map(|&c| c as usize).sum();
+        let names: Vec<String> = framebuffer.channel_names().map(str::to_string).collect();
+
+        for name in names {
+            let mut flat = vec![0.0f32; total];
+            self.read_channel(&name, &counts, &mut flat)?;
+
+            let pixels = framebuffer
+                .channel_mut(&name)
+                .expect("channel came from this buffer's own channel list");
+            let mut offset = 0;
+            for (pixel, &count) in pixels.iter_mut().zip(counts.iter()) {
+                pixel.copy_from_slice(&flat[offset..offset + count as usize]);
+                offset += count as usize;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Drop for DeepScanLineInputFile<'a> {
+    fn drop(&mut self) {
+        unsafe { CEXR_DeepScanLineInputFile_delete(self.handle) };
+        unsafe { CEXR_IStream_delete(self.istream) };
+    }
+}
@@ -1,5 +1,10 @@
 //! Input file types.
 
+mod deep_scan_line_input_file;
+mod multi_part_input_file;
+mod streaming_input_file;
+mod tiled_input_file;
+
 use std::cmp::min;
 use std::ffi::CStr;
 use std::io::{Read, Seek};
@@ -10,11 +15,18 @@ use libc::c_char;
 
 use openexr_sys::*;
 
+use cexr_type_aliases::Box2i;
 use error::*;
-use frame_buffer::FrameBufferMut;
+use frame_buffer::{AllChannelsFrameBuffer, FrameBufferMut, SamplingMode};
 use Header;
+use ReadLimits;
 use stream_io::{read_stream, seek_stream};
 
+pub use self::deep_scan_line_input_file::DeepScanLineInputFile;
+pub use self::multi_part_input_file::MultiPartInputFile;
+pub use self::streaming_input_file::StreamingInputFile;
+pub use self::tiled_input_file::TiledInputFile;
+
 /// Reads any kind of OpenEXR file.
 ///
 /// `InputFile` is a bit unique in that it doesn't care what kind of OpenEXR
@@ -23,8 +35,7 @@ use stream_io::{read_stream, seek_stream};
 ///
 /// Special features like tiles, mipmaps, and deep image data will not be
 /// available even if they are present in the file.  To gain access to those
-/// features you need to use the other input file types (not yet implemented,
-/// sorry!).
+/// features, use `TiledInputFile` or `DeepScanLineInputFile` instead.
 ///
 /// # Examples
 ///
@@ -81,7 +92,7 @@ impl<'a> InputFile<'a> {
 
             if error != 0 {
                 let msg = unsafe { CStr::from_ptr(error_out) };
-                return Err(Error::Generic(msg.to_string_lossy().into_owned()));
+                return Err(Error::C(msg.to_string_lossy().into_owned()));
             } else {
                 out
             }
@@ -92,7 +103,7 @@ impl<'a> InputFile<'a> {
         let error = unsafe { CEXR_InputFile_from_stream(istream_ptr, 1, &mut out, &mut error_out) };
         if error != 0 {
             let msg = unsafe { CStr::from_ptr(error_out) };
-            Err(Error::Generic(msg.to_string_lossy().into_owned()))
+            Err(Error::C(msg.to_string_lossy().into_owned()))
         } else {
             Ok(InputFile {
                    handle: out,
@@ -129,7 +140,7 @@ impl<'a> InputFile<'a> {
         let error = unsafe { CEXR_InputFile_from_stream(istream_ptr, 1, &mut out, &mut error_out) };
         if error != 0 {
             let msg = unsafe { CStr::from_ptr(error_out) };
-            Err(Error::Generic(msg.to_string_lossy().into_owned()))
+            Err(Error::C(msg.to_string_lossy().into_owned()))
         } else {
             Ok(InputFile {
                    handle: out,
@@ -170,12 +181,10 @@ impl<'a> InputFile<'a> {
 
         // Validation
         if self.header().data_dimensions() != framebuffer.dimensions() {
-            return Err(Error::Generic(format!("framebuffer size {}x{} does not match \
-                                              image dimensions {}x{}",
-                                              framebuffer.dimensions().0,
-                                              framebuffer.dimensions().1,
-                                              self.header().data_dimensions().0,
-                                              self.header().data_dimensions().1)));
+            return Err(Error::DimensionMismatch {
+                expected: self.header().data_dimensions(),
+                got: framebuffer.dimensions(),
+            });
         }
 
         self.header().validate_framebuffer_for_input(framebuffer)?;
@@ -188,7 +197,7 @@ impl<'a> InputFile<'a> {
         };
         if error != 0 {
             let msg = unsafe { CStr::from_ptr(error_out) };
-            return Err(Error::Generic(msg.to_string_lossy().into_owned()));
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
         }
 
         // Read the image data
@@ -200,7 +209,7 @@ impl<'a> InputFile<'a> {
         };
         if error != 0 {
             let msg = unsafe { CStr::from_ptr(error_out) };
-            Err(Error::Generic(msg.to_string_lossy().into_owned()))
+            Err(Error::C(msg.to_string_lossy().into_owned()))
         } else {
             Ok(())
         }
@@ -244,10 +253,10 @@ impl<'a> InputFile<'a> {
                 "Cannot start reading past last scanline.");
 
         if self.header().data_dimensions().0 != framebuffer.dimensions().0 {
-            return Err(Error::Generic(format!("framebuffer width {} does not match\
-                                              image width {}",
-                                              framebuffer.dimensions().0,
-                                              self.header().data_dimensions().0)));
+            return Err(Error::DimensionMismatch {
+                expected: (self.header().data_dimensions().0, framebuffer.dimensions().1),
+                got: framebuffer.dimensions(),
+            });
         }
 
         self.header().validate_framebuffer_for_input(framebuffer)?;
@@ -271,7 +280,7 @@ impl<'a> InputFile<'a> {
         };
         if error != 0 {
             let msg = unsafe { CStr::from_ptr(error_out) };
-            return Err(Error::Generic(msg.to_string_lossy().into_owned()));
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
         }
 
         // Read the image data
@@ -280,12 +289,174 @@ impl<'a> InputFile<'a> {
         };
         if error != 0 {
             let msg = unsafe { CStr::from_ptr(error_out) };
-            Err(Error::Generic(msg.to_string_lossy().into_owned()))
+            Err(Error::C(msg.to_string_lossy().into_owned()))
         } else {
             Ok((scanline_read_count))
         }
     }
 
+    /// Reads the file into every pixel of `region`, producing out-of-data-
+    /// window pixels according to `mode` instead of only the channels'
+    /// fill values.
+    ///
+    /// `region` must fully contain the file's data window -- typically
+    /// it's the file's display window, for treating a cropped-data-window
+    /// file as a full-display-window image without a separate post-process
+    /// pass. Pixels inside the data window are read normally; pixels in
+    /// `region` but outside it are produced by sampling the data window's
+    /// edge: `SamplingMode::Clamp` replicates the nearest edge pixel,
+    /// `SamplingMode::Repeat` tiles the data window, and
+    /// `SamplingMode::Black` leaves them at each channel's fill value (the
+    /// same behavior as plain `read_pixels`).
+    ///
+    /// Returns a freshly allocated `AllChannelsFrameBuffer`, with one
+    /// channel per channel in the file's header, sized to `region`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `region` doesn't fully contain the data window,
+    /// or if there is an I/O error.
+    pub fn read_pixels_sampled(
+        &mut self,
+        region: Box2i,
+        mode: SamplingMode,
+    ) -> Result<AllChannelsFrameBuffer> {
+        let (dw_min_x, dw_min_y, dw_max_x, dw_max_y) = {
+            let dw = self.header().data_window();
+            (dw.min.x, dw.min.y, dw.max.x, dw.max.y)
+        };
+
+        if region.min.x > dw_min_x || region.min.y > dw_min_y || region.max.x < dw_max_x ||
+            region.max.y < dw_max_y
+        {
+            return Err(Error::Unsupported(
+                "read_pixels_sampled's region must fully contain the file's data window"
+                    .to_string(),
+            ));
+        }
+
+        let region_dimensions = (
+            (region.max.x - region.min.x + 1) as u32,
+            (region.max.y - region.min.y + 1) as u32,
+        );
+        let data_dimensions = (
+            (dw_max_x - dw_min_x + 1) as u32,
+            (dw_max_y - dw_min_y + 1) as u32,
+        );
+        let offset = (
+            (dw_min_x - region.min.x) as u32,
+            (dw_min_y - region.min.y) as u32,
+        );
+
+        let mut buffer = AllChannelsFrameBuffer::new_for_region(
+            self.header(),
+            region_dimensions.0,
+            region_dimensions.1,
+        );
+
+        {
+            let mut fb = buffer.frame_buffer_mut_region(
+                offset.0,
+                offset.1,
+                data_dimensions.0,
+                data_dimensions.1,
+                (dw_min_x, dw_min_y),
+            );
+            self.read_pixels(&mut fb)?;
+        }
+
+        if mode != SamplingMode::Black {
+            buffer.extend_edges(offset.0, offset.1, data_dimensions.0, data_dimensions.1, mode);
+        }
+
+        Ok(buffer)
+    }
+
+    /// Reads only the scanline blocks selected by `predicate`, invoking
+    /// `callback` with each decoded block's pixel data and its starting
+    /// scanline as it completes.
+    ///
+    /// Scanlines are grouped into blocks according to
+    /// `Header::block_scanline_count()`, which depends on the file's
+    /// compression method (1 scanline for `ZIPS_COMPRESSION`, 16 for
+    /// `ZIP_COMPRESSION`, 32/256 for `DWAA`/`DWAB_COMPRESSION`).
+    /// `predicate` is called with the starting scanline and scanline count
+    /// of each block in the image, in order; blocks for which it returns
+    /// `false` are skipped without being decompressed.  `setup_channels` is
+    /// called once per selected block to describe which channels should be
+    /// read into it, the same way you would set up any other
+    /// `FrameBufferMut`.
+    ///
+    /// Blocks are read and decoded one at a time, in order, each into its
+    /// own block-sized `FrameBufferMut` -- `callback` is free to borrow
+    /// `&mut` state across calls, since there's no concurrent decoding here
+    /// to race with it. This lets huge images be processed incrementally
+    /// without allocating one framebuffer for the whole image, but it does
+    /// not parallelize decoding; for that, read the whole image at once
+    /// with `read_pixels` after raising `set_global_thread_count()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is an I/O error, or if `callback` returns
+    /// one.
+    pub fn read_blocks<P, S, C>(
+        &mut self,
+        mut predicate: P,
+        setup_channels: S,
+        mut callback: C,
+    ) -> Result<()>
+    where
+        P: FnMut(u32, u32) -> bool,
+        S: Fn(&mut FrameBufferMut),
+        C: FnMut(u32, FrameBufferMut) -> Result<()>,
+    {
+        let (width, height) = self.header().data_dimensions();
+        let block_size = self.header().block_scanline_count();
+
+        let mut scanline = 0;
+        while scanline < height {
+            let block_height = min(block_size, height - scanline);
+            if predicate(scanline, block_height) {
+                let mut fb = FrameBufferMut::new(width, block_height);
+                setup_channels(&mut fb);
+                self.read_pixels_partial(scanline, &mut fb)?;
+                callback(scanline, fb)?;
+            }
+            scanline += block_height;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new `InputFile` from a slice of bytes that may come from an
+    /// untrusted source, bounds-checking the header's reported dimensions,
+    /// channel count, and implied framebuffer size against the default
+    /// `ReadLimits` before returning.
+    ///
+    /// This is otherwise identical to `from_slice`, except that a
+    /// maliciously crafted header that reports an enormous resolution or
+    /// channel count -- which would otherwise lead to huge or overflowing
+    /// allocations in downstream `FrameBuffer` code before OpenEXR itself
+    /// has a chance to reject the file -- is instead rejected here with an
+    /// `Error`.
+    pub fn from_untrusted_slice(slice: &[u8]) -> Result<InputFile> {
+        Self::from_untrusted_slice_with_limits(slice, &ReadLimits::default())
+    }
+
+    /// Identical to `from_untrusted_slice`, but checks the header against
+    /// `limits` instead of the defaults.
+    ///
+    /// Pass `&ReadLimits::unlimited()` to disable these checks entirely for
+    /// input that is already known to be trustworthy.
+    pub fn from_untrusted_slice_with_limits(
+        slice: &[u8],
+        limits: &ReadLimits,
+    ) -> Result<InputFile> {
+        let file = Self::from_slice(slice)?;
+        file.header().validate_untrusted_bounds(limits)?;
+        Ok(file)
+    }
+
     /// Access to the file's header.
     pub fn header(&self) -> &Header {
         &self.header_ref
@@ -0,0 +1,196 @@
+use std::ffi::CStr;
+use std::io::{Read, Seek};
+use std::marker::PhantomData;
+use std::ptr;
+
+use libc::c_int;
+
+use openexr_sys::*;
+
+use error::*;
+use frame_buffer::FrameBufferMut;
+use stream_io::{read_stream, seek_stream};
+use Header;
+
+/// Reads multi-part OpenEXR files, where a single file holds several
+/// independent images (called "parts").
+///
+/// Multi-part files are how OpenEXR represents things like stereo (`left`/
+/// `right`) views or separate render passes that are nonetheless meant to
+/// travel together as one file: each part has its own `Header` and its own
+/// set of channels, read independently of the others.  Views are usually
+/// distinguished by the `view.channel` dotted naming convention within a
+/// part's channel list (see `Header::views()`/`Header::channels_in_view()`),
+/// rather than by splitting views across parts.
+pub struct MultiPartInputFile<'a> {
+    handle: *mut CEXR_MultiPartInputFile,
+    headers: Vec<Header>,
+    istream: *mut CEXR_IStream,
+    _phantom_1: PhantomData<CEXR_MultiPartInputFile>,
+    _phantom_2: PhantomData<&'a mut ()>, // Represents the borrowed reader
+
+    // NOTE: Because we don't know what type the reader might be, it's important
+    // that this struct remains neither Sync nor Send.  Please don't implement
+    // them!
+}
+
+impl<'a> MultiPartInputFile<'a> {
+    /// Creates a new `MultiPartInputFile` from any `Read + Seek` type
+    /// (typically a `std::fs::File`).
+    ///
+    /// Note: this seeks to byte 0 before reading.
+    pub fn new<T: 'a>(reader: &mut T) -> Result<MultiPartInputFile>
+    where
+        T: Read + Seek,
+    {
+        let istream_ptr = {
+            let read_ptr = read_stream::<T>;
+            let seekp_ptr = seek_stream::<T>;
+
+            let mut error_out = ptr::null();
+            let mut out = ptr::null_mut();
+            let error = unsafe {
+                CEXR_IStream_from_reader(
+                    reader as *mut T as *mut _,
+                    Some(read_ptr),
+                    Some(seekp_ptr),
+                    &mut out,
+                    &mut error_out,
+                )
+            };
+
+            if error != 0 {
+                let msg = unsafe { CStr::from_ptr(error_out) };
+                return Err(Error::C(msg.to_string_lossy().into_owned()));
+            } else {
+                out
+            }
+        };
+
+        let mut error_out = ptr::null();
+        let mut out = ptr::null_mut();
+        let error = unsafe {
+            CEXR_MultiPartInputFile_from_stream(istream_ptr, 1, &mut out, &mut error_out)
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        let part_count = unsafe { CEXR_MultiPartInputFile_parts(out) } as usize;
+        let headers = (0..part_count)
+            .map(|part| Header {
+                // NOTE: We're casting to *mut here to satisfy the field's
+                // type, but importantly we only return a const & of the
+                // Header so it retains const semantics.
+                handle: unsafe { CEXR_MultiPartInputFile_header(out, part as c_int) } as *mut CEXR_Header,
+                owned: false,
+                _phantom: PhantomData,
+            })
+            .collect();
+
+        Ok(MultiPartInputFile {
+            handle: out,
+            headers,
+            istream: istream_ptr,
+            _phantom_1: PhantomData,
+            _phantom_2: PhantomData,
+        })
+    }
+
+    /// Returns the number of parts (independent images) in the file.
+    pub fn parts(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Access to the header of part `part`.
+    pub fn header(&self, part: usize) -> &Header {
+        &self.headers[part]
+    }
+
+    /// Returns the view names declared in `part`'s header, same as
+    /// `self.header(part).views()`.
+    pub fn views(&self, part: usize) -> impl Iterator<Item = &str> {
+        self.header(part).views()
+    }
+
+    /// Returns the full (prefixed) channel names belonging to `view_name`
+    /// within `part`, same as `self.header(part).view_channel_names(view_name)`.
+    pub fn view_channel_names<'b>(
+        &'b self,
+        part: usize,
+        view_name: &'b str,
+    ) -> Result<Vec<&'b str>> {
+        self.header(part).view_channel_names(view_name)
+    }
+
+    /// Reads the entirety of part `part` into `framebuffer` at once.
+    ///
+    /// Any channels in `framebuffer` that are not present in the part will
+    /// be filled with their default fill value.
+    ///
+    /// # Errors
+    ///
+    /// This function expects `framebuffer` to have the same resolution as
+    /// `part`'s data window, and for any same-named channels to have
+    /// matching types and subsampling.
+    ///
+    /// It will also return an error if there is an I/O error.
+    pub fn read_pixels(&mut self, part: usize, framebuffer: &mut FrameBufferMut) -> Result<()> {
+        // ^^^ NOTE: it's not obvious, but this does indeed need to take self
+        // as &mut to be safe.  Even though it is not conceptually modifying
+        // the thing (typically a file) that it's reading from, it still has
+        // a cursor getting incremented etc. during reads, so the reference
+        // needs to be unique to avoid unsafe aliasing.
+
+        if self.header(part).data_dimensions() != framebuffer.dimensions() {
+            return Err(Error::DimensionMismatch {
+                expected: self.header(part).data_dimensions(),
+                got: framebuffer.dimensions(),
+            });
+        }
+
+        self.header(part).validate_framebuffer_for_input(framebuffer)?;
+
+        let mut error_out = ptr::null();
+
+        let error = unsafe {
+            CEXR_MultiPartInputFile_set_framebuffer(
+                self.handle,
+                part as c_int,
+                framebuffer.handle_mut(),
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        let error = unsafe {
+            CEXR_MultiPartInputFile_read_pixels(
+                self.handle,
+                part as c_int,
+                self.header(part).data_window().min.y,
+                self.header(part).data_window().max.y,
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'a> Drop for MultiPartInputFile<'a> {
+    fn drop(&mut self) {
+        // Drop the (non-owning) per-part headers before the file that owns
+        // the C++ objects they point into.
+        self.headers.clear();
+        unsafe { CEXR_MultiPartInputFile_delete(self.handle) };
+        unsafe { CEXR_IStream_delete(self.istream) };
+    }
+}
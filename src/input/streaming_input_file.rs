@@ -0,0 +1,262 @@
+//! A forward-only scanline reader for sources that don't support `Seek`,
+//! such as pipes, stdin, or sockets.
+
+use std::cmp::min;
+use std::ffi::CStr;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::ptr;
+
+use openexr_sys::*;
+
+use error::*;
+use frame_buffer::FrameBufferMut;
+use stream_io::{read_stream, seek_stream};
+use Header;
+
+/// Reads scanline OpenEXR files from a `Read`-only source, top to bottom.
+///
+/// `InputFile::new` requires `Read + Seek`, which rules out reading
+/// straight off a pipe, a TCP stream, or stdin.  `StreamingInputFile`
+/// works around this by wrapping the reader in an internal cursor that
+/// only ever seeks forward (by reading and discarding bytes), and which
+/// errors loudly if OpenEXR ever asks it to seek backward.
+///
+/// In practice that only happens for file structures that genuinely need
+/// random access -- tiles and mip/rip maps.  To keep this type safe by
+/// construction rather than failing unpredictably partway through a read,
+/// `new()` itself rejects tiled files up front with `Error::Unsupported`.
+/// (Multi-part files can't currently be detected ahead of time through
+/// this crate's bindings, so a multi-part file will instead fail with a
+/// backward-seek I/O error the first time its part-offset table is
+/// consulted out of order.)
+///
+/// `read_pixels` only ever reads the next unread scanlines in order; there
+/// is no equivalent of `InputFile::read_pixels_partial`'s arbitrary
+/// `starting_scanline`, since that would require seeking backward through
+/// already-consumed input.
+pub struct StreamingInputFile<'a> {
+    handle: *mut CEXR_InputFile,
+    header_ref: Header,
+    istream: *mut CEXR_IStream,
+    reader: *mut c_void,
+    drop_reader: unsafe fn(*mut c_void),
+    next_scanline: u32,
+    _phantom_1: PhantomData<CEXR_InputFile>,
+    _phantom_2: PhantomData<&'a mut ()>, // Represents the owned, boxed reader
+
+    // NOTE: Because we don't know what type the reader might be, it's important
+    // that this struct remains neither Sync nor Send.  Please don't implement
+    // them!
+}
+
+impl<'a> StreamingInputFile<'a> {
+    /// Creates a new `StreamingInputFile` from any `Read` type.
+    ///
+    /// Returns `Error::Unsupported` if the file turns out to be tiled,
+    /// since tiles can be stored in any order and reading them correctly
+    /// requires random access.
+    pub fn new<T: 'a>(reader: T) -> Result<StreamingInputFile<'a>>
+    where
+        T: Read,
+    {
+        // Boxed so its address is stable for the C++ side to call back
+        // into, regardless of where this `StreamingInputFile` itself ends
+        // up living.
+        let forward_only = Box::into_raw(Box::new(ForwardOnly {
+            inner: reader,
+            pos: 0,
+        }));
+
+        let istream_ptr = {
+            let read_ptr = read_stream::<ForwardOnly<T>>;
+            let seekp_ptr = seek_stream::<ForwardOnly<T>>;
+
+            let mut error_out = ptr::null();
+            let mut out = ptr::null_mut();
+            let error = unsafe {
+                CEXR_IStream_from_reader(
+                    forward_only as *mut _,
+                    Some(read_ptr),
+                    Some(seekp_ptr),
+                    &mut out,
+                    &mut error_out,
+                )
+            };
+
+            if error != 0 {
+                drop(unsafe { Box::from_raw(forward_only) });
+                let msg = unsafe { CStr::from_ptr(error_out) };
+                return Err(Error::C(msg.to_string_lossy().into_owned()));
+            } else {
+                out
+            }
+        };
+
+        let mut error_out = ptr::null();
+        let mut out = ptr::null_mut();
+        let error = unsafe { CEXR_InputFile_from_stream(istream_ptr, 1, &mut out, &mut error_out) };
+        if error != 0 {
+            drop(unsafe { Box::from_raw(forward_only) });
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        let header_ref = Header {
+            // NOTE: We're casting to *mut here to satisfy the field's
+            // type, but importantly we only return a const & of the
+            // Header so it retains const semantics.
+            handle: unsafe { CEXR_InputFile_header(out) } as *mut CEXR_Header,
+            owned: false,
+            _phantom: PhantomData,
+        };
+
+        if header_ref.is_tiled() {
+            unsafe { CEXR_InputFile_delete(out) };
+            drop(unsafe { Box::from_raw(forward_only) });
+            return Err(Error::Unsupported(
+                "StreamingInputFile cannot read tiled files; they require random access"
+                    .to_string(),
+            ));
+        }
+
+        Ok(StreamingInputFile {
+            handle: out,
+            header_ref: header_ref,
+            istream: istream_ptr,
+            reader: forward_only as *mut c_void,
+            drop_reader: drop_forward_only::<T>,
+            next_scanline: 0,
+            _phantom_1: PhantomData,
+            _phantom_2: PhantomData,
+        })
+    }
+
+    /// Access to the file's header.
+    pub fn header(&self) -> &Header {
+        &self.header_ref
+    }
+
+    /// Reads the next scanlines into `framebuffer`, continuing from
+    /// wherever the previous call (if any) left off.
+    ///
+    /// `framebuffer` must have the same horizontal resolution as the file.
+    /// On success returns the number of scanlines read, which will be
+    /// less than `framebuffer`'s height only when the end of the image is
+    /// reached.  Once every scanline has been read, subsequent calls
+    /// return `Ok(0)`.
+    pub fn read_pixels(&mut self, framebuffer: &mut FrameBufferMut) -> Result<u32> {
+        // ^^^ As with InputFile::read_pixels_partial, this takes self as
+        // &mut because reading advances the underlying reader's cursor,
+        // even though it's not conceptually mutating the image.
+
+        let (width, height) = self.header().data_dimensions();
+        if width != framebuffer.dimensions().0 {
+            return Err(Error::DimensionMismatch {
+                expected: (width, framebuffer.dimensions().1),
+                got: framebuffer.dimensions(),
+            });
+        }
+
+        let scanline_read_count = min(height - self.next_scanline, framebuffer.dimensions().1);
+        if scanline_read_count == 0 {
+            return Ok(0);
+        }
+
+        self.header().validate_framebuffer_for_input(framebuffer)?;
+
+        let start_scanline = self.header().data_window().min.y + self.next_scanline as i32;
+        let end_scanline = start_scanline + scanline_read_count as i32 - 1;
+
+        let mut error_out = ptr::null();
+
+        let error = unsafe {
+            let offset_fb =
+                CEXR_FrameBuffer_copy_and_offset_scanlines(framebuffer.handle_mut(), self.next_scanline);
+            let err = CEXR_InputFile_set_framebuffer(self.handle, offset_fb, &mut error_out);
+            CEXR_FrameBuffer_delete(offset_fb);
+            err
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        let error = unsafe {
+            CEXR_InputFile_read_pixels(self.handle, start_scanline, end_scanline, &mut error_out)
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        self.next_scanline += scanline_read_count;
+        Ok(scanline_read_count)
+    }
+}
+
+impl<'a> Drop for StreamingInputFile<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            CEXR_InputFile_delete(self.handle);
+            CEXR_IStream_delete(self.istream);
+            (self.drop_reader)(self.reader);
+        }
+    }
+}
+
+// Frees the boxed `ForwardOnly<T>` pointed to by `ptr`, with `T` baked in
+// via monomorphization so `StreamingInputFile` itself doesn't need to be
+// generic over the reader type.
+unsafe fn drop_forward_only<T>(ptr: *mut c_void) {
+    drop(Box::from_raw(ptr as *mut ForwardOnly<T>));
+}
+
+// Wraps a `Read`-only source so it can satisfy the `Read + Seek` bound the
+// stream-bridging callbacks in `stream_io` expect, without actually
+// supporting backward seeks: forward seeks are implemented by reading and
+// discarding bytes, and any other seek is reported as an I/O error.
+struct ForwardOnly<T> {
+    inner: T,
+    pos: u64,
+}
+
+impl<T: Read> Read for ForwardOnly<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<T: Read> Seek for ForwardOnly<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => n,
+            SeekFrom::Current(n) if n >= 0 => self.pos + n as u64,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "this stream only supports seeking forward from the start",
+                ))
+            }
+        };
+
+        if target < self.pos {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "cannot seek backward from byte {} to {} on a forward-only stream \
+                     (the file likely needs random access, e.g. because it's tiled or \
+                     multi-part)",
+                    self.pos, target
+                ),
+            ));
+        }
+
+        io::copy(&mut (&mut self.inner).take(target - self.pos), &mut io::sink())?;
+        self.pos = target;
+        Ok(self.pos)
+    }
+}
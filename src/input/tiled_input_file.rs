@@ -0,0 +1,269 @@
+use std::ffi::CStr;
+use std::io::{Read, Seek};
+use std::marker::PhantomData;
+use std::ptr;
+
+use libc::c_int;
+
+use openexr_sys::*;
+
+use cexr_type_aliases::Box2i;
+use error::*;
+use frame_buffer::FrameBufferMut;
+use stream_io::{read_stream, seek_stream};
+use Header;
+
+/// Reads tiled OpenEXR files, including mip/rip-map images.
+///
+/// Unlike `InputFile`, `TiledInputFile` reads image data one tile at a time
+/// and gives access to whichever resolution level is needed, rather than
+/// only the full-resolution image.
+pub struct TiledInputFile<'a> {
+    handle: *mut CEXR_TiledInputFile,
+    header_ref: Header,
+    istream: *mut CEXR_IStream,
+    _phantom_1: PhantomData<CEXR_TiledInputFile>,
+    _phantom_2: PhantomData<&'a mut ()>, // Represents the borrowed reader
+
+    // NOTE: Because we don't know what type the reader might be, it's important
+    // that this struct remains neither Sync nor Send.  Please don't implement
+    // them!
+}
+
+impl<'a> TiledInputFile<'a> {
+    /// Creates a new `TiledInputFile` from any `Read + Seek` type (typically
+    /// a `std::fs::File`).
+    ///
+    /// Note: this seeks to byte 0 before reading.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file isn't actually tiled.
+    pub fn new<T: 'a>(reader: &mut T) -> Result<TiledInputFile>
+    where
+        T: Read + Seek,
+    {
+        let istream_ptr = {
+            let read_ptr = read_stream::<T>;
+            let seekp_ptr = seek_stream::<T>;
+
+            let mut error_out = ptr::null();
+            let mut out = ptr::null_mut();
+            let error = unsafe {
+                CEXR_IStream_from_reader(
+                    reader as *mut T as *mut _,
+                    Some(read_ptr),
+                    Some(seekp_ptr),
+                    &mut out,
+                    &mut error_out,
+                )
+            };
+
+            if error != 0 {
+                let msg = unsafe { CStr::from_ptr(error_out) };
+                return Err(Error::C(msg.to_string_lossy().into_owned()));
+            } else {
+                out
+            }
+        };
+
+        let mut error_out = ptr::null();
+        let mut out = ptr::null_mut();
+        let error =
+            unsafe { CEXR_TiledInputFile_from_stream(istream_ptr, 1, &mut out, &mut error_out) };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(TiledInputFile {
+                handle: out,
+                header_ref: Header {
+                    // NOTE: We're casting to *mut here to satisfy the
+                    // field's type, but importantly we only return a
+                    // const & of the Header so it retains const semantics.
+                    handle: unsafe { CEXR_TiledInputFile_header(out) } as *mut CEXR_Header,
+                    owned: false,
+                    _phantom: PhantomData,
+                },
+                istream: istream_ptr,
+                _phantom_1: PhantomData,
+                _phantom_2: PhantomData,
+            })
+        }
+    }
+
+    /// Reads a single tile into `framebuffer`.
+    ///
+    /// `level` is the `(x, y)` mip/rip-map level to read from; for
+    /// single-level (non-mipmapped) tiled images this is always `(0, 0)`.
+    /// `dx`/`dy` are the tile's coordinates within that level, in tiles
+    /// (not pixels).
+    ///
+    /// Any channels in `framebuffer` that are not present in the file will
+    /// be filled with their default fill value.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is an I/O error.
+    pub fn read_tile(
+        &mut self,
+        dx: u32,
+        dy: u32,
+        level: (u32, u32),
+        framebuffer: &mut FrameBufferMut,
+    ) -> Result<()> {
+        // ^^^ NOTE: it's not obvious, but this does indeed need to take self as
+        // &mut to be safe.  Even though it is not conceptually modifying the
+        // thing (typically a file) that it's reading from, it still has a
+        // cursor getting incremented etc. during reads, so the reference needs
+        // to be unique to avoid unsafe aliasing.
+
+        self.header().validate_framebuffer_for_input(framebuffer)?;
+
+        let mut error_out = ptr::null();
+
+        let error = unsafe {
+            CEXR_TiledInputFile_set_framebuffer(self.handle, framebuffer.handle_mut(), &mut error_out)
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        let error = unsafe {
+            CEXR_TiledInputFile_read_tile(
+                self.handle,
+                dx as c_int,
+                dy as c_int,
+                level.0 as c_int,
+                level.1 as c_int,
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reads a rectangular range of tiles, from `dx_range.0` to
+    /// `dx_range.1` and `dy_range.0` to `dy_range.1` inclusive, at `level`,
+    /// into `framebuffer`.
+    ///
+    /// Equivalent to calling `read_tile()` for each tile in the range, but
+    /// only sets `framebuffer` once rather than once per tile.
+    ///
+    /// Any channels in `framebuffer` that are not present in the file will
+    /// be filled with their default fill value.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is an I/O error.
+    pub fn read_tiles(
+        &mut self,
+        dx_range: (u32, u32),
+        dy_range: (u32, u32),
+        level: (u32, u32),
+        framebuffer: &mut FrameBufferMut,
+    ) -> Result<()> {
+        self.header().validate_framebuffer_for_input(framebuffer)?;
+
+        let mut error_out = ptr::null();
+
+        let error = unsafe {
+            CEXR_TiledInputFile_set_framebuffer(self.handle, framebuffer.handle_mut(), &mut error_out)
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        for dy in dy_range.0..=dy_range.1 {
+            for dx in dx_range.0..=dx_range.1 {
+                let error = unsafe {
+                    CEXR_TiledInputFile_read_tile(
+                        self.handle,
+                        dx as c_int,
+                        dy as c_int,
+                        level.0 as c_int,
+                        level.1 as c_int,
+                        &mut error_out,
+                    )
+                };
+                if error != 0 {
+                    let msg = unsafe { CStr::from_ptr(error_out) };
+                    return Err(Error::C(msg.to_string_lossy().into_owned()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of tiles in the x and y directions at `level`.
+    pub fn level_dimensions(&self, level: (u32, u32)) -> (u32, u32) {
+        let mut x = 0;
+        let mut y = 0;
+        unsafe {
+            CEXR_TiledInputFile_level_dimensions(
+                self.handle,
+                level.0 as c_int,
+                level.1 as c_int,
+                &mut x,
+                &mut y,
+            )
+        };
+        (x as u32, y as u32)
+    }
+
+    /// Returns the number of levels in the x direction.
+    ///
+    /// For `LevelMode::OneLevel` and `LevelMode::MipmapLevels` images this
+    /// is the same as `num_y_levels()`; for `LevelMode::RipmapLevels`
+    /// images it may differ.
+    pub fn num_x_levels(&self) -> u32 {
+        unsafe { CEXR_TiledInputFile_num_x_levels(self.handle) as u32 }
+    }
+
+    /// Returns the number of levels in the y direction.
+    pub fn num_y_levels(&self) -> u32 {
+        unsafe { CEXR_TiledInputFile_num_y_levels(self.handle) as u32 }
+    }
+
+    /// Returns the `(x, y)` tile size, in pixels.
+    ///
+    /// Convenience wrapper around `Header::tile_size()`, which is always
+    /// `Some` for a file this type was able to open.
+    pub fn tile_size(&self) -> (u32, u32) {
+        self.header()
+            .tile_size()
+            .expect("a successfully-opened TiledInputFile's header always has a tile description")
+    }
+
+    /// Returns the `(x, y)` number of levels, i.e. `(num_x_levels(),
+    /// num_y_levels())`.
+    pub fn num_levels(&self) -> (u32, u32) {
+        (self.num_x_levels(), self.num_y_levels())
+    }
+
+    /// Returns the data window, in pixels, of the given level.
+    pub fn level_data_window(&self, level: (u32, u32)) -> Box2i {
+        unsafe {
+            CEXR_TiledInputFile_level_data_window(self.handle, level.0 as c_int, level.1 as c_int)
+        }
+    }
+
+    /// Access to the file's header.
+    pub fn header(&self) -> &Header {
+        &self.header_ref
+    }
+}
+
+impl<'a> Drop for TiledInputFile<'a> {
+    fn drop(&mut self) {
+        unsafe { CEXR_TiledInputFile_delete(self.handle) };
+        unsafe { CEXR_IStream_delete(self.istream) };
+    }
+}
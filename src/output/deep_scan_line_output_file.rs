@@ -0,0 +1,218 @@
+use std::ffi::{CStr, CString};
+use std::io::{Seek, Write};
+use std::marker::PhantomData;
+use std::ptr;
+
+use libc::{c_float, c_int};
+
+use openexr_sys::*;
+
+use error::*;
+use frame_buffer::DeepFrameBuffer;
+use stream_io::{seek_stream, write_stream};
+use Header;
+
+/// Writes deep scanline OpenEXR files.
+///
+/// This is the write-side counterpart to `DeepScanLineInputFile`. Deep
+/// images store a variable number of samples per pixel, so writing one is
+/// a two-step process:
+///
+/// 1. `set_sample_counts()` tells the file how many samples each pixel
+///    will have, so it knows how to size each channel's data as it's
+///    written.
+/// 2. `write_channel()` writes one channel's samples for every pixel,
+///    using those same counts to know where each pixel's samples start
+///    and end.
+pub struct DeepScanLineOutputFile<'a> {
+    handle: *mut CEXR_DeepScanLineOutputFile,
+    header_ref: Header,
+    ostream: *mut CEXR_OStream,
+    _phantom_1: PhantomData<CEXR_DeepScanLineOutputFile>,
+    _phantom_2: PhantomData<&'a mut ()>, // Represents the borrowed writer
+
+    // NOTE: Because we don't know what type the writer might be, it's important
+    // that this struct remains neither Sync nor Send.  Please don't implement
+    // them!
+}
+
+impl<'a> DeepScanLineOutputFile<'a> {
+    /// Creates a new `DeepScanLineOutputFile` from any `Write + Seek` type
+    /// (typically a `std::fs::File`) and `header`.
+    ///
+    /// Note: this seeks to byte 0 before writing.
+    pub fn new<T: 'a>(writer: &'a mut T, header: &Header) -> Result<DeepScanLineOutputFile<'a>>
+    where
+        T: Write + Seek,
+    {
+        let ostream_ptr = {
+            let write_ptr = write_stream::<T>;
+            let seekp_ptr = seek_stream::<T>;
+
+            let mut error_out = ptr::null();
+            let mut out = ptr::null_mut();
+            let error = unsafe {
+                CEXR_OStream_from_writer(
+                    writer as *mut T as *mut _,
+                    Some(write_ptr),
+                    Some(seekp_ptr),
+                    &mut out,
+                    &mut error_out,
+                )
+            };
+
+            if error != 0 {
+                let msg = unsafe { CStr::from_ptr(error_out) };
+                return Err(Error::C(msg.to_string_lossy().into_owned()));
+            } else {
+                out
+            }
+        };
+
+        let mut error_out = ptr::null();
+        let mut out = ptr::null_mut();
+        let error = unsafe {
+            // NOTE: we don't need to keep a copy of the header, because this
+            // function makes a deep copy that is stored in the
+            // CEXR_DeepScanLineOutputFile.
+            CEXR_DeepScanLineOutputFile_from_stream(ostream_ptr, header.handle, 1, &mut out, &mut error_out)
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(DeepScanLineOutputFile {
+                handle: out,
+                header_ref: Header {
+                    // NOTE: We're casting to *mut here to satisfy the
+                    // field's type, but importantly we only return a
+                    // const & of the Header so it retains const semantics.
+                    handle: unsafe { CEXR_DeepScanLineOutputFile_header(out) } as *mut CEXR_Header,
+                    owned: false,
+                    _phantom: PhantomData,
+                },
+                ostream: ostream_ptr,
+                _phantom_1: PhantomData,
+                _phantom_2: PhantomData,
+            })
+        }
+    }
+
+    /// Access to the file's header.
+    pub fn header(&self) -> &Header {
+        &self.header_ref
+    }
+
+    /// Sets the number of samples that will be written at each pixel.
+    ///
+    /// `counts` must have exactly `width * height` elements, in data-window
+    /// row-major order (the same order `ScanlineOutputFile::write_pixels` uses).
+    /// This must be called before `write_channel()`.
+    pub fn set_sample_counts(&mut self, counts: &[u32]) -> Result<()> {
+        let (width, height) = self.header().data_dimensions();
+        let required = width as usize * height as usize;
+        if counts.len() != required {
+            return Err(Error::Unsupported(format!(
+                "sample count buffer has {} elements, but the {}x{} data window requires {}",
+                counts.len(),
+                width,
+                height,
+                required
+            )));
+        }
+
+        let counts: Vec<c_int> = counts.iter().map(|&c| c as c_int).collect();
+        let mut error_out = ptr::null();
+        let error = unsafe {
+            CEXR_DeepScanLineOutputFile_set_pixel_sample_counts(
+                self.handle,
+                counts.as_ptr(),
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes channel `name`'s deep samples for every pixel from `samples`.
+    ///
+    /// `counts` must be the same buffer previously passed to
+    /// `set_sample_counts()`; `samples` must have exactly
+    /// `counts.iter().sum()` elements, laid out the same way
+    /// `DeepScanLineInputFile::read_channel()` fills them.
+    pub fn write_channel(&mut self, name: &str, counts: &[u32], samples: &[f32]) -> Result<()> {
+        let (width, height) = self.header().data_dimensions();
+        if counts.len() != width as usize * height as usize {
+            return Err(Error::Unsupported(format!(
+                "sample count buffer has {} elements, but the {}x{} data window requires {}",
+                counts.len(),
+                width,
+                height,
+                width as usize * height as usize
+            )));
+        }
+
+        let total: usize = counts.iter().map(|&c| c as usize).sum();
+        if samples.len() != total {
+            return Err(Error::Unsupported(format!(
+                "sample buffer has {} elements, but the counts sum to {}",
+                samples.len(),
+                total
+            )));
+        }
+
+        let mut sample_pointers: Vec<*const c_float> = Vec::with_capacity(counts.len());
+        let mut offset = 0usize;
+        for &count in counts {
+            sample_pointers.push(unsafe { samples.as_ptr().add(offset) });
+            offset += count as usize;
+        }
+
+        let c_name = CString::new(name).unwrap();
+        let mut error_out = ptr::null();
+        let error = unsafe {
+            CEXR_DeepScanLineOutputFile_write_channel(
+                self.handle,
+                c_name.as_ptr(),
+                sample_pointers.as_ptr(),
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes every channel of `framebuffer`.
+    ///
+    /// This is `set_sample_counts()` and `write_channel()` rolled into one
+    /// call, mirroring `DeepScanLineInputFile::read_pixels()`.
+    pub fn write_pixels(&mut self, framebuffer: &DeepFrameBuffer) -> Result<()> {
+        self.set_sample_counts(framebuffer.sample_counts())?;
+
+        let counts = framebuffer.sample_counts();
+        for name in framebuffer.channel_names() {
+            let pixels = framebuffer
+                .channel(name)
+                .expect("channel came from this buffer's own channel list");
+            let flat: Vec<f32> = pixels.iter().flat_map(|p| p.iter().cloned()).collect();
+            self.write_channel(name, counts, &flat)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Drop for DeepScanLineOutputFile<'a> {
+    fn drop(&mut self) {
+        unsafe { CEXR_DeepScanLineOutputFile_delete(self.handle) };
+        unsafe { CEXR_OStream_delete(self.ostream) };
+    }
+}
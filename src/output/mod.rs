@@ -1,4 +1,9 @@
+mod deep_scan_line_output_file;
+mod multi_part_output_file;
+mod scanline_output_file;
 mod scanline_writer;
+mod stream_writer;
+mod tiled_output_file;
 
 use std::ffi::CString;
 use std::marker::PhantomData;
@@ -9,7 +14,11 @@ use openexr_sys::*;
 use cexr_type_aliases::*;
 use error::*;
 
+pub use self::deep_scan_line_output_file::DeepScanLineOutputFile;
+pub use self::multi_part_output_file::MultiPartOutputFile;
+pub use self::scanline_output_file::{ScanlineOutputFile, UnseekableScanlineOutputFile};
 pub use self::scanline_writer::ScanlineWriter;
+pub use self::tiled_output_file::TiledOutputFile;
 
 
 pub struct OutputFile {
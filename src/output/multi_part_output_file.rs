@@ -0,0 +1,190 @@
+use std::ffi::CStr;
+use std::io::{Seek, Write};
+use std::marker::PhantomData;
+use std::ptr;
+
+use libc::c_int;
+
+use openexr_sys::*;
+
+use error::*;
+use frame_buffer::FrameBuffer;
+use stream_io::{seek_stream, write_stream};
+use Header;
+
+/// Writes multi-part OpenEXR files, where a single file holds several
+/// independent images (called "parts").
+///
+/// Each part is written independently of the others, with its own `Header`
+/// (supplied up front to `new()`) and its own channel data.  This is the
+/// counterpart to `MultiPartInputFile`, and is the way to produce e.g.
+/// stereo (`left`/`right`) or multi-pass EXR files that a single-part
+/// `ScanlineOutputFile` can't represent.
+pub struct MultiPartOutputFile<'a> {
+    handle: *mut CEXR_MultiPartOutputFile,
+    headers: Vec<Header>,
+    ostream: *mut CEXR_OStream,
+    _phantom_1: PhantomData<CEXR_MultiPartOutputFile>,
+    _phantom_2: PhantomData<&'a mut ()>, // Represents the borrowed writer
+
+    // NOTE: Because we don't know what type the writer might be, it's important
+    // that this struct remains neither Sync nor Send.  Please don't implement
+    // them!
+}
+
+impl<'a> MultiPartOutputFile<'a> {
+    /// Creates a new `MultiPartOutputFile` from any `Write + Seek` type
+    /// (typically a `std::fs::File`) and one `Header` per part.
+    ///
+    /// Note: this seeks to byte 0 before writing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `headers` is empty.
+    pub fn new<T: 'a>(writer: &'a mut T, headers: &[Header]) -> Result<MultiPartOutputFile<'a>>
+    where
+        T: Write + Seek,
+    {
+        if headers.is_empty() {
+            return Err(Error::Unsupported(
+                "a MultiPartOutputFile needs at least one part header".to_string(),
+            ));
+        }
+
+        let ostream_ptr = {
+            let write_ptr = write_stream::<T>;
+            let seekp_ptr = seek_stream::<T>;
+
+            let mut error_out = ptr::null();
+            let mut out = ptr::null_mut();
+            let error = unsafe {
+                CEXR_OStream_from_writer(
+                    writer as *mut T as *mut _,
+                    Some(write_ptr),
+                    Some(seekp_ptr),
+                    &mut out,
+                    &mut error_out,
+                )
+            };
+
+            if error != 0 {
+                let msg = unsafe { CStr::from_ptr(error_out) };
+                return Err(Error::C(msg.to_string_lossy().into_owned()));
+            } else {
+                out
+            }
+        };
+
+        let header_handles: Vec<*const CEXR_Header> =
+            headers.iter().map(|h| h.handle as *const CEXR_Header).collect();
+
+        let mut error_out = ptr::null();
+        let mut out = ptr::null_mut();
+        let error = unsafe {
+            // NOTE: we don't need to keep our own copies of the headers,
+            // because this function makes deep copies that are stored in
+            // the CEXR_MultiPartOutputFile.
+            CEXR_MultiPartOutputFile_from_stream(
+                ostream_ptr,
+                header_handles.as_ptr(),
+                header_handles.len() as c_int,
+                1,
+                &mut out,
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        let part_headers = (0..header_handles.len())
+            .map(|part| Header {
+                // NOTE: We're casting to *mut here to satisfy the field's
+                // type, but importantly we only return a const & of the
+                // Header so it retains const semantics.
+                handle: unsafe { CEXR_MultiPartOutputFile_header(out, part as c_int) } as *mut CEXR_Header,
+                owned: false,
+                _phantom: PhantomData,
+            })
+            .collect();
+
+        Ok(MultiPartOutputFile {
+            handle: out,
+            headers: part_headers,
+            ostream: ostream_ptr,
+            _phantom_1: PhantomData,
+            _phantom_2: PhantomData,
+        })
+    }
+
+    /// Returns the number of parts in the file.
+    pub fn parts(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Access to the header of part `part`.
+    pub fn header(&self, part: usize) -> &Header {
+        &self.headers[part]
+    }
+
+    /// Writes all of part `part`'s scanlines from `framebuffer`.
+    ///
+    /// # Errors
+    ///
+    /// This function expects `framebuffer` to have the same resolution as
+    /// part `part`'s data window, and for its channels to match part
+    /// `part`'s header channels in type and subsampling.  It will also
+    /// return an error if there is an I/O error.
+    pub fn write_pixels(&mut self, part: usize, framebuffer: &FrameBuffer) -> Result<()> {
+        if self.header(part).data_dimensions() != framebuffer.dimensions() {
+            return Err(Error::DimensionMismatch {
+                expected: self.header(part).data_dimensions(),
+                got: framebuffer.dimensions(),
+            });
+        }
+
+        self.header(part).validate_framebuffer_for_output(framebuffer)?;
+
+        let mut error_out = ptr::null();
+
+        let error = unsafe {
+            CEXR_MultiPartOutputFile_set_framebuffer(
+                self.handle,
+                part as c_int,
+                framebuffer.handle(),
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        let (_, height) = self.header(part).data_dimensions();
+        let error = unsafe {
+            CEXR_MultiPartOutputFile_write_pixels(
+                self.handle,
+                part as c_int,
+                height as c_int,
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'a> Drop for MultiPartOutputFile<'a> {
+    fn drop(&mut self) {
+        // Drop the (non-owning) per-part headers before the file that owns
+        // the C++ objects they point into.
+        self.headers.clear();
+        unsafe { CEXR_MultiPartOutputFile_delete(self.handle) };
+        unsafe { CEXR_OStream_delete(self.ostream) };
+    }
+}
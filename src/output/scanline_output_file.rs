@@ -1,12 +1,14 @@
 use std::ffi::CStr;
-use std::io::{Seek, Write};
+use std::io::{Cursor, Seek, Write};
 use std::marker::PhantomData;
+use std::os::raw::{c_char, c_void};
 use std::ptr;
 
 use openexr_sys::*;
 
 use error::*;
 use frame_buffer::FrameBuffer;
+use output::stream_writer::{self, StreamWriter, DEFAULT_BUFFER_CAPACITY};
 use stream_io::{seek_stream, write_stream};
 use Header;
 
@@ -45,6 +47,9 @@ pub struct ScanlineOutputFile<'a> {
     handle: *mut CEXR_OutputFile,
     header_ref: Header,
     ostream: *mut CEXR_OStream,
+    stream_writer: *mut c_void,
+    drop_stream_writer: unsafe fn(*mut c_void),
+    take_stream_error: unsafe fn(*mut c_void) -> Option<Error>,
     scanlines_written: u32,
     _phantom_1: PhantomData<CEXR_OutputFile>,
     _phantom_2: PhantomData<&'a mut ()>, // Represents the borrowed writer
@@ -56,22 +61,84 @@ pub struct ScanlineOutputFile<'a> {
 
 impl<'a> ScanlineOutputFile<'a> {
     /// Creates a new `ScanlineOutputFile` from any `Write + Seek` type
-    /// (typically a `std::fs::File`) and `header`.
+    /// (typically a `std::fs::File`) and `header`, with the default
+    /// write-coalescing buffer capacity.
     ///
     /// Note: this seeks to byte 0 before writing.
     pub fn new<T: 'a>(writer: &'a mut T, header: &Header) -> Result<ScanlineOutputFile<'a>>
     where
         T: Write + Seek,
     {
+        ScanlineOutputFile::with_buffer_capacity(writer, header, DEFAULT_BUFFER_CAPACITY)
+    }
+
+    /// Creates a new `ScanlineOutputFile`, as per `new()`, but with a
+    /// specific write-coalescing buffer capacity.
+    ///
+    /// OpenEXR writes compressed scanline chunks and offset-table entries
+    /// in many small pieces, which the returned file buffers internally
+    /// before passing them on to `writer` in fewer, larger writes.  Pass
+    /// `0` to disable buffering and write straight through to `writer` on
+    /// every call.
+    pub fn with_buffer_capacity<T: 'a>(
+        writer: &'a mut T,
+        header: &Header,
+        buffer_capacity: usize,
+    ) -> Result<ScanlineOutputFile<'a>>
+    where
+        T: Write + Seek,
+    {
+        ScanlineOutputFile::with_buffer_capacity_and_limit(writer, header, buffer_capacity, None)
+    }
+
+    /// Creates a new `ScanlineOutputFile`, as per `new()`, but refusing to
+    /// write more than `max_bytes` total.
+    ///
+    /// Once a write would push the file past `max_bytes`, the write fails
+    /// with `Error::LimitExceeded` and OpenEXR aborts the encode. This
+    /// protects services that encode EXRs on behalf of untrusted or remote
+    /// callers from producing unbounded output.
+    pub fn new_with_byte_limit<T: 'a>(
+        writer: &'a mut T,
+        header: &Header,
+        max_bytes: u64,
+    ) -> Result<ScanlineOutputFile<'a>>
+    where
+        T: Write + Seek,
+    {
+        ScanlineOutputFile::with_buffer_capacity_and_limit(
+            writer,
+            header,
+            DEFAULT_BUFFER_CAPACITY,
+            Some(max_bytes),
+        )
+    }
+
+    /// Creates a new `ScanlineOutputFile` with both a specific
+    /// write-coalescing buffer capacity and an optional byte limit. See
+    /// `with_buffer_capacity()` and `new_with_byte_limit()`.
+    pub fn with_buffer_capacity_and_limit<T: 'a>(
+        writer: &'a mut T,
+        header: &Header,
+        buffer_capacity: usize,
+        max_bytes: Option<u64>,
+    ) -> Result<ScanlineOutputFile<'a>>
+    where
+        T: Write + Seek,
+    {
+        let stream_writer_ptr = Box::into_raw(Box::new(
+            StreamWriter::with_buffer_capacity_and_limit(writer, buffer_capacity, max_bytes),
+        ));
+
         let ostream_ptr = {
-            let write_ptr = write_stream::<T>;
-            let seekp_ptr = seek_stream::<T>;
+            let write_ptr = stream_writer::write::<T>;
+            let seekp_ptr = stream_writer::seekp::<T>;
 
             let mut error_out = ptr::null();
             let mut out = ptr::null_mut();
             let error = unsafe {
                 CEXR_OStream_from_writer(
-                    writer as *mut T as *mut _,
+                    stream_writer_ptr as *mut _,
                     Some(write_ptr),
                     Some(seekp_ptr),
                     &mut out,
@@ -80,8 +147,9 @@ impl<'a> ScanlineOutputFile<'a> {
             };
 
             if error != 0 {
+                unsafe { drop(Box::from_raw(stream_writer_ptr)) };
                 let msg = unsafe { CStr::from_ptr(error_out) };
-                return Err(Error::Generic(msg.to_string_lossy().into_owned()));
+                return Err(Error::C(msg.to_string_lossy().into_owned()));
             } else {
                 out
             }
@@ -95,8 +163,12 @@ impl<'a> ScanlineOutputFile<'a> {
             CEXR_OutputFile_from_stream(ostream_ptr, header.handle, 1, &mut out, &mut error_out)
         };
         if error != 0 {
+            unsafe {
+                CEXR_OStream_delete(ostream_ptr);
+                drop(Box::from_raw(stream_writer_ptr));
+            }
             let msg = unsafe { CStr::from_ptr(error_out) };
-            Err(Error::Generic(msg.to_string_lossy().into_owned()))
+            Err(Error::C(msg.to_string_lossy().into_owned()))
         } else {
             Ok(ScanlineOutputFile {
                 handle: out,
@@ -109,6 +181,9 @@ impl<'a> ScanlineOutputFile<'a> {
                     _phantom: PhantomData,
                 },
                 ostream: ostream_ptr,
+                stream_writer: stream_writer_ptr as *mut c_void,
+                drop_stream_writer: drop_stream_writer::<T>,
+                take_stream_error: take_stream_error::<T>,
                 scanlines_written: 0,
                 _phantom_1: PhantomData,
                 _phantom_2: PhantomData,
@@ -130,36 +205,7 @@ impl<'a> ScanlineOutputFile<'a> {
     ///   call to either this or `write_pixels_incremental`.
     /// * There is an I/O error.
     pub fn write_pixels(&mut self, framebuffer: &FrameBuffer) -> Result<()> {
-        // Validation
-        if self.scanlines_written != 0 {
-            return Err(Error::Generic(format!(
-                "{} scanlines have already been \
-                 written, cannot do a full image write",
-                self.scanlines_written
-            )));
-        }
-
-        if self.header().data_dimensions() != framebuffer.dimensions() {
-            return Err(Error::Generic(format!(
-                "framebuffer size {}x{} does not match image dimensions {}x{}",
-                framebuffer.dimensions().0,
-                framebuffer.dimensions().1,
-                self.header().data_dimensions().0,
-                self.header().data_dimensions().1
-            )));
-        }
-
-        if self.header().data_origin() != framebuffer.origin() {
-            return Err(Error::Generic(format!(
-                "framebuffer origin {}x{} does not match image origin {}x{}",
-                framebuffer.origin().0,
-                framebuffer.origin().1,
-                self.header().data_origin().0,
-                self.header().data_origin().1
-            )));
-        }
-
-        self.header().validate_framebuffer_for_output(framebuffer)?;
+        validate_full_write(self.scanlines_written, self.header(), framebuffer)?;
 
         // Set up the framebuffer with the image
         let mut error_out = ptr::null();
@@ -168,8 +214,7 @@ impl<'a> ScanlineOutputFile<'a> {
             CEXR_OutputFile_set_framebuffer(self.handle, framebuffer.handle(), &mut error_out)
         };
         if error != 0 {
-            let msg = unsafe { CStr::from_ptr(error_out) };
-            return Err(Error::Generic(msg.to_string_lossy().into_owned()));
+            return Err(self.output_file_error(error_out));
         }
 
         // Write out the image data
@@ -181,8 +226,7 @@ impl<'a> ScanlineOutputFile<'a> {
             )
         };
         if error != 0 {
-            let msg = unsafe { CStr::from_ptr(error_out) };
-            Err(Error::Generic(msg.to_string_lossy().into_owned()))
+            Err(self.output_file_error(error_out))
         } else {
             self.scanlines_written = self.header().data_dimensions().1;
             Ok(())
@@ -213,39 +257,298 @@ impl<'a> ScanlineOutputFile<'a> {
     /// * `framebuffer` contains more scanlines than remain to be written.
     /// * There is an I/O error.
     pub fn write_pixels_incremental(&mut self, framebuffer: &FrameBuffer) -> Result<()> {
-        // Validation
-        if self.scanlines_written == self.header().data_dimensions().1 {
-            return Err(Error::Generic(
-                "All scanlines have already been \
-                 written, cannot do another incremental write"
-                    .to_string(),
-            ));
+        validate_incremental_write(self.scanlines_written, self.header(), framebuffer)?;
+
+        // Set up the framebuffer with the image
+        let mut error_out = ptr::null();
+
+        let error = unsafe {
+            let offset_fb = CEXR_FrameBuffer_copy_and_offset_scanlines(
+                framebuffer.handle(),
+                self.scanlines_written,
+            );
+            let err = CEXR_OutputFile_set_framebuffer(self.handle, offset_fb, &mut error_out);
+            CEXR_FrameBuffer_delete(offset_fb);
+            err
+        };
+        if error != 0 {
+            return Err(self.output_file_error(error_out));
         }
 
-        if framebuffer.dimensions().1 > (self.header().data_dimensions().1 - self.scanlines_written)
-        {
-            return Err(Error::Generic(format!(
-                "framebuffer contains {} \
-                 scanlines, but only {} scanlines remain to be written",
-                framebuffer.dimensions().1,
-                self.header().data_dimensions().1 - self.scanlines_written
-            )));
+        // Write out the image data
+        let error = unsafe {
+            CEXR_OutputFile_write_pixels(
+                self.handle,
+                framebuffer.dimensions().1 as i32,
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            Err(self.output_file_error(error_out))
+        } else {
+            self.scanlines_written += framebuffer.dimensions().1;
+            Ok(())
         }
+    }
 
-        if framebuffer.dimensions().0 != self.header().data_dimensions().0 {
-            return Err(Error::Generic(format!(
-                "framebuffer width {} does not match\
-                 image width {}",
-                framebuffer.dimensions().0,
-                self.header().data_dimensions().0
-            )));
+    /// Access to the file's header.
+    pub fn header(&self) -> &Header {
+        &self.header_ref
+    }
+
+    // Builds the `Error` for a failed `CEXR_OutputFile_*` call: if the
+    // underlying writer recorded a real `io::Error`, that's preserved as
+    // `Error::Io` so callers can match on its `ErrorKind`; otherwise this
+    // falls back to OpenEXR's own text message.
+    fn output_file_error(&mut self, error_out: *const c_char) -> Error {
+        if let Some(err) = unsafe { (self.take_stream_error)(self.stream_writer) } {
+            err
+        } else {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Error::C(msg.to_string_lossy().into_owned())
         }
+    }
 
-        self.header().validate_framebuffer_for_output(framebuffer)?;
+    /// Creates a scanline output file backed by a `Write`-only sink that
+    /// doesn't implement `Seek` -- see `UnseekableScanlineOutputFile` for
+    /// details and the memory tradeoff this makes.
+    pub fn new_unseekable<T: Write>(
+        writer: T,
+        header: &Header,
+    ) -> Result<UnseekableScanlineOutputFile<T>> {
+        UnseekableScanlineOutputFile::new(writer, header)
+    }
+}
+
+// Shared validation for `write_pixels`: `framebuffer` must cover the whole
+// image, at the image's own origin, and nothing may have been written yet.
+// Used by both `ScanlineOutputFile` and `UnseekableScanlineOutputFile` so
+// their full-image and incremental write methods can't drift apart.
+fn validate_full_write(
+    scanlines_written: u32,
+    header: &Header,
+    framebuffer: &FrameBuffer,
+) -> Result<()> {
+    if scanlines_written != 0 {
+        return Err(Error::Unsupported(format!(
+            "{} scanlines have already been \
+             written, cannot do a full image write",
+            scanlines_written
+        )));
+    }
+
+    if header.data_dimensions() != framebuffer.dimensions() {
+        return Err(Error::DimensionMismatch {
+            expected: header.data_dimensions(),
+            got: framebuffer.dimensions(),
+        });
+    }
+
+    if header.data_origin() != framebuffer.origin() {
+        return Err(Error::Unsupported(format!(
+            "framebuffer origin {}x{} does not match image origin {}x{}",
+            framebuffer.origin().0,
+            framebuffer.origin().1,
+            header.data_origin().0,
+            header.data_origin().1
+        )));
+    }
+
+    header.validate_framebuffer_for_output(framebuffer)
+}
+
+// Shared validation for `write_pixels_incremental`: `framebuffer` must match
+// the image's horizontal resolution and fit within the scanlines remaining
+// to be written. See `validate_full_write` for why this is a free function.
+fn validate_incremental_write(
+    scanlines_written: u32,
+    header: &Header,
+    framebuffer: &FrameBuffer,
+) -> Result<()> {
+    if scanlines_written == header.data_dimensions().1 {
+        return Err(Error::Unsupported(
+            "All scanlines have already been \
+             written, cannot do another incremental write"
+                .to_string(),
+        ));
+    }
+
+    if framebuffer.dimensions().1 > (header.data_dimensions().1 - scanlines_written) {
+        return Err(Error::Unsupported(format!(
+            "framebuffer contains {} \
+             scanlines, but only {} scanlines remain to be written",
+            framebuffer.dimensions().1,
+            header.data_dimensions().1 - scanlines_written
+        )));
+    }
+
+    if framebuffer.dimensions().0 != header.data_dimensions().0 {
+        return Err(Error::DimensionMismatch {
+            expected: (header.data_dimensions().0, framebuffer.dimensions().1),
+            got: framebuffer.dimensions(),
+        });
+    }
+
+    header.validate_framebuffer_for_output(framebuffer)
+}
+
+impl<'a> Drop for ScanlineOutputFile<'a> {
+    fn drop(&mut self) {
+        // The stream writer must outlive both the OpenEXR handle and the
+        // ostream, since they may still call back into it (e.g. to flush
+        // buffered writes) while being torn down.
+        unsafe { CEXR_OutputFile_delete(self.handle) };
+        unsafe { CEXR_OStream_delete(self.ostream) };
+        unsafe { (self.drop_stream_writer)(self.stream_writer) };
+    }
+}
+
+// Drops the boxed `StreamWriter<T>` behind `stream_writer`'s type-erased
+// pointer.  Flushes any buffered, unwritten bytes via `StreamWriter`'s own
+// `Drop` impl.
+unsafe fn drop_stream_writer<T: Write + Seek>(ptr: *mut c_void) {
+    drop(Box::from_raw(ptr as *mut StreamWriter<T>));
+}
+
+// Takes the most recent write/seek failure recorded on the `StreamWriter<T>`
+// at `stream_writer`'s type-erased pointer, if any, preferring a byte-limit
+// violation over a bare I/O error since it's the more specific diagnosis.
+unsafe fn take_stream_error<T: Write + Seek>(ptr: *mut c_void) -> Option<Error> {
+    let stream_writer = &mut *(ptr as *mut StreamWriter<T>);
+    if let Some((limit, attempted)) = stream_writer.take_limit_exceeded() {
+        Some(Error::LimitExceeded { limit, attempted })
+    } else {
+        stream_writer.take_io_error().map(Error::Io)
+    }
+}
+
+/// Writes scanline OpenEXR files to `Write`-only sinks that don't support
+/// `Seek` -- sockets, stdout, compression encoders, HTTP request bodies,
+/// and the like.
+///
+/// OpenEXR genuinely needs to seek backward while writing, to patch the
+/// chunk offset table and header once the pixel data that precedes them is
+/// known. Since a `Write`-only sink can't support that, this type instead
+/// writes the entire encoded file into an in-memory `Cursor<Vec<u8>>` --
+/// which does support `Seek` -- and only drains that buffer into the real
+/// sink, in one sequential forward-only pass, once writing is finished.
+///
+/// This trades memory for seekability: the whole encoded file accumulates
+/// in RAM until `finish()` (or `Drop`) drains it, so it isn't a good fit
+/// for very large images, and a misbehaving or malicious caller can use it
+/// to force unbounded memory growth. Consider capping the write with
+/// something like `ScanlineOutputFile::new_with_byte_limit`'s approach if
+/// the data being encoded isn't trusted.
+pub struct UnseekableScanlineOutputFile<T: Write> {
+    handle: *mut CEXR_OutputFile,
+    header_ref: Header,
+    ostream: *mut CEXR_OStream,
+    cursor: Box<Cursor<Vec<u8>>>,
+    writer: Option<T>,
+    scanlines_written: u32,
+}
+
+impl<T: Write> UnseekableScanlineOutputFile<T> {
+    /// Creates a new `UnseekableScanlineOutputFile` from any `Write` type
+    /// and `header`.
+    pub fn new(writer: T, header: &Header) -> Result<UnseekableScanlineOutputFile<T>> {
+        let mut cursor = Box::new(Cursor::new(Vec::new()));
+        // `cursor`'s heap allocation has a stable address that doesn't move
+        // even if the `Box` itself is moved into the struct below, so it's
+        // safe to hand this raw pointer to the C++ side as a write context.
+        let cursor_ptr = cursor.as_mut() as *mut Cursor<Vec<u8>>;
+
+        let ostream_ptr = {
+            let write_ptr = write_stream::<Cursor<Vec<u8>>>;
+            let seekp_ptr = seek_stream::<Cursor<Vec<u8>>>;
+
+            let mut error_out = ptr::null();
+            let mut out = ptr::null_mut();
+            let error = unsafe {
+                CEXR_OStream_from_writer(
+                    cursor_ptr as *mut _,
+                    Some(write_ptr),
+                    Some(seekp_ptr),
+                    &mut out,
+                    &mut error_out,
+                )
+            };
+
+            if error != 0 {
+                let msg = unsafe { CStr::from_ptr(error_out) };
+                return Err(Error::C(msg.to_string_lossy().into_owned()));
+            } else {
+                out
+            }
+        };
+
+        let mut error_out = ptr::null();
+        let mut out = ptr::null_mut();
+        let error = unsafe {
+            CEXR_OutputFile_from_stream(ostream_ptr, header.handle, 1, &mut out, &mut error_out)
+        };
+        if error != 0 {
+            unsafe { CEXR_OStream_delete(ostream_ptr) };
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(UnseekableScanlineOutputFile {
+                handle: out,
+                header_ref: Header {
+                    // NOTE: We're casting to *mut here to satisfy the
+                    // field's type, but importantly we only return a
+                    // const & of the Header so it retains const semantics.
+                    handle: unsafe { CEXR_OutputFile_header(out) } as *mut CEXR_Header,
+                    owned: false,
+                    _phantom: PhantomData,
+                },
+                ostream: ostream_ptr,
+                cursor: cursor,
+                writer: Some(writer),
+                scanlines_written: 0,
+            })
+        }
+    }
+
+    /// Writes the entire image at once from `framebuffer`.
+    ///
+    /// See `ScanlineOutputFile::write_pixels` for details and errors.
+    pub fn write_pixels(&mut self, framebuffer: &FrameBuffer) -> Result<()> {
+        validate_full_write(self.scanlines_written, self.header(), framebuffer)?;
 
-        // Set up the framebuffer with the image
         let mut error_out = ptr::null();
+        let error = unsafe {
+            CEXR_OutputFile_set_framebuffer(self.handle, framebuffer.handle(), &mut error_out)
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        let error = unsafe {
+            CEXR_OutputFile_write_pixels(
+                self.handle,
+                framebuffer.dimensions().1 as i32,
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            self.scanlines_written = self.header().data_dimensions().1;
+            Ok(())
+        }
+    }
+
+    /// Writes the image incrementally over multiple calls.
+    ///
+    /// See `ScanlineOutputFile::write_pixels_incremental` for details and
+    /// errors.
+    pub fn write_pixels_incremental(&mut self, framebuffer: &FrameBuffer) -> Result<()> {
+        validate_incremental_write(self.scanlines_written, self.header(), framebuffer)?;
 
+        let mut error_out = ptr::null();
         let error = unsafe {
             let offset_fb = CEXR_FrameBuffer_copy_and_offset_scanlines(
                 framebuffer.handle(),
@@ -257,10 +560,9 @@ impl<'a> ScanlineOutputFile<'a> {
         };
         if error != 0 {
             let msg = unsafe { CStr::from_ptr(error_out) };
-            return Err(Error::Generic(msg.to_string_lossy().into_owned()));
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
         }
 
-        // Write out the image data
         let error = unsafe {
             CEXR_OutputFile_write_pixels(
                 self.handle,
@@ -270,7 +572,7 @@ impl<'a> ScanlineOutputFile<'a> {
         };
         if error != 0 {
             let msg = unsafe { CStr::from_ptr(error_out) };
-            Err(Error::Generic(msg.to_string_lossy().into_owned()))
+            Err(Error::C(msg.to_string_lossy().into_owned()))
         } else {
             self.scanlines_written += framebuffer.dimensions().1;
             Ok(())
@@ -281,11 +583,54 @@ impl<'a> ScanlineOutputFile<'a> {
     pub fn header(&self) -> &Header {
         &self.header_ref
     }
+
+    /// Finishes writing the OpenEXR encoding, drains the in-memory spool
+    /// buffer into the underlying writer, and returns it so callers can
+    /// chain further operations (e.g. calling `finish()` on a compression
+    /// encoder).
+    ///
+    /// Until this is called (or `self` is dropped), nothing has actually
+    /// reached the underlying writer -- the whole encoded file has been
+    /// accumulating in memory instead.
+    pub fn finish(mut self) -> Result<T> {
+        self.delete_openexr_handles();
+        let mut writer = self
+            .writer
+            .take()
+            .expect("UnseekableScanlineOutputFile::finish() called more than once");
+        writer.write_all(self.cursor.get_ref())?;
+        writer.flush()?;
+        Ok(writer)
+    }
+
+    /// Equivalent to `finish()`, but discards the underlying writer.
+    pub fn into_inner(self) -> Result<()> {
+        self.finish().map(|_| ())
+    }
+
+    // Tears down the OpenEXR/ostream handles, flushing any writes OpenEXR
+    // hasn't yet made into `self.cursor`. Idempotent: safe to call whether
+    // or not `finish()` has already run.
+    fn delete_openexr_handles(&mut self) {
+        if !self.handle.is_null() {
+            unsafe {
+                CEXR_OutputFile_delete(self.handle);
+                CEXR_OStream_delete(self.ostream);
+            }
+            self.handle = ptr::null_mut();
+        }
+    }
 }
 
-impl<'a> Drop for ScanlineOutputFile<'a> {
+impl<T: Write> Drop for UnseekableScanlineOutputFile<T> {
     fn drop(&mut self) {
-        unsafe { CEXR_OutputFile_delete(self.handle) };
-        unsafe { CEXR_OStream_delete(self.ostream) };
+        self.delete_openexr_handles();
+        if let Some(mut writer) = self.writer.take() {
+            // Best-effort: there's no way to propagate an I/O error out of
+            // `Drop`, and discarding the error here mirrors `StreamWriter`'s
+            // own buffer-flushing `Drop` impl.
+            let _ = writer.write_all(self.cursor.get_ref());
+            let _ = writer.flush();
+        }
     }
 }
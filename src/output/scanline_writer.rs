@@ -32,7 +32,7 @@ impl ScanlineWriter {
         };
         if error != 0 {
             let msg = unsafe { CStr::from_ptr(error_out) };
-            Err(Error::Generic(msg.to_string_lossy().into_owned()))
+            Err(Error::C(msg.to_string_lossy().into_owned()))
         } else {
             Ok(ScanlineWriter {
                    handle: out,
@@ -60,7 +60,7 @@ impl ScanlineWriter {
         };
         if error != 0 {
             let msg = unsafe { CStr::from_ptr(error_out) };
-            Err(Error::Generic(msg.to_string_lossy().into_owned()))
+            Err(Error::C(msg.to_string_lossy().into_owned()))
         } else {
             Ok(())
         }
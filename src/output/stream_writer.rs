@@ -1,65 +1,230 @@
-use std::os::raw::{c_char, c_int};
-use std::io::{Write, Seek, SeekFrom};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::os::raw::{c_char, c_int, c_void};
 use std::slice;
 
-/// A pointer to this is passed to the OpenEXR C++ API for writing
-/// to the IO source it represents.  It hides T from the C++ API
-/// and also keeps track of the cursor position, which Rust's Seek
-/// trait doesn't expose.
+/// Default size, in bytes, of `StreamWriter`'s internal write-coalescing
+/// buffer -- matches `std::io::BufWriter`'s default.
+pub const DEFAULT_BUFFER_CAPACITY: usize = 8 * 1024;
+
+/// The context object passed to the OpenEXR C++ API for writing to the IO
+/// source a `ScanlineOutputFile` was created from.  It hides `T` from the
+/// C++ API, tracks the cursor position (which `Seek` doesn't let us query
+/// directly), and coalesces OpenEXR's many small writes into fewer, larger
+/// ones.
+///
+/// OpenEXR's `OStream` calls `write()` once per compressed scanline/chunk
+/// and again for each offset-table slot, which turns into a storm of tiny
+/// `write_all` syscalls on an unbuffered sink (a raw `TcpStream`, a pipe, a
+/// non-`BufWriter` file). Following `std::io::BufWriter`'s design,
+/// `StreamWriter` appends those writes to an internal buffer instead,
+/// flushing only when OpenEXR seeks elsewhere -- which it does to patch
+/// the offset table and header after the bulk of the data has been
+/// written -- or when the buffer fills up or is dropped.
 ///
-/// Note: the reason we can't just pass the pointer to T directly
-/// is because it could be a fat pointer to a trait object.
+/// Note: the reason we can't just pass a pointer to `T` directly is
+/// because it could be a fat pointer to a trait object.
 pub struct StreamWriter<'a, T: 'a + Write + Seek> {
     writer: &'a mut T,
-    cursor_pos: usize,
+    cursor_pos: u64,
+    buffer: Vec<u8>,
+    buffer_origin: u64,
+    buffer_capacity: usize,
+
+    // The `io::Error` behind the most recent failed `write`/`seekp` call, if
+    // any. OpenEXR's C++ side only ever sees a 0/1/2 return code, so we
+    // stash the real error here and `ScanlineOutputFile` picks it back up
+    // after a `CEXR_OutputFile_*` call reports failure, rather than falling
+    // back to OpenEXR's generic text message.
+    io_error: Option<io::Error>,
+
+    // The total number of bytes a write may ever reach, or `None` for no
+    // limit. Checked against `high_water_mark` on every `write`, so a
+    // seek-and-overwrite within already-written bytes isn't penalized
+    // twice.
+    max_bytes: Option<u64>,
+
+    // The largest `cursor_pos` has ever reached after a successful write.
+    high_water_mark: u64,
+
+    // `(limit, attempted)` from the most recent write that was refused for
+    // exceeding `max_bytes`, if any.
+    limit_exceeded: Option<(u64, u64)>,
 }
 
 impl<'a, T: 'a + Write + Seek> StreamWriter<'a, T> {
+    /// Creates a `StreamWriter` with the default buffer capacity and no
+    /// byte limit.
     pub fn new<'b>(writer: &'b mut T) -> StreamWriter<'b, T> {
+        StreamWriter::with_buffer_capacity(writer, DEFAULT_BUFFER_CAPACITY)
+    }
+
+    /// Creates a `StreamWriter` with a specific write-coalescing buffer
+    /// capacity and no byte limit. Pass `0` to disable buffering and write
+    /// straight through to `writer` on every call.
+    pub fn with_buffer_capacity<'b>(writer: &'b mut T, capacity: usize) -> StreamWriter<'b, T> {
+        StreamWriter::with_buffer_capacity_and_limit(writer, capacity, None)
+    }
+
+    /// Creates a `StreamWriter` with a specific write-coalescing buffer
+    /// capacity and an optional cap, in bytes, on the total size of the
+    /// write. A write that would push the high-water mark of written file
+    /// offsets past `max_bytes` is refused and recorded for
+    /// `take_limit_exceeded()`, which `ScanlineOutputFile` reports as
+    /// `Error::LimitExceeded`.
+    pub fn with_buffer_capacity_and_limit<'b>(
+        writer: &'b mut T,
+        capacity: usize,
+        max_bytes: Option<u64>,
+    ) -> StreamWriter<'b, T> {
         writer
             .seek(SeekFrom::Start(0))
             .expect("Couldn't seek to zero.");
         StreamWriter {
             writer: writer,
             cursor_pos: 0,
+            buffer: Vec::with_capacity(capacity),
+            buffer_origin: 0,
+            buffer_capacity: capacity,
+            io_error: None,
+            max_bytes: max_bytes,
+            high_water_mark: 0,
+            limit_exceeded: None,
         }
     }
+
+    /// Takes the `io::Error` behind the most recent failed write or seek, if
+    /// any, leaving `None` in its place.
+    pub fn take_io_error(&mut self) -> Option<io::Error> {
+        self.io_error.take()
+    }
+
+    /// Takes the `(limit, attempted)` pair behind the most recent write
+    /// refused for exceeding `max_bytes`, if any, leaving `None` in its
+    /// place.
+    pub fn take_limit_exceeded(&mut self) -> Option<(u64, u64)> {
+        self.limit_exceeded.take()
+    }
+
+    // Writes out any buffered bytes and clears the buffer. A no-op if
+    // nothing is buffered.
+    fn flush_buffer(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let result = self.writer.write_all(&self.buffer);
+        self.buffer.clear();
+        self.buffer_origin = self.cursor_pos;
+        result
+    }
+
+    // Records `error` as the most recently observed I/O failure and
+    // translates it into the 0/1/2 C-callback return code.
+    fn record_error(&mut self, error: io::Error) -> c_int {
+        let code = if error.raw_os_error().is_some() { 1 } else { 2 };
+        self.io_error = Some(error);
+        code
+    }
+}
+
+impl<'a, T: 'a + Write + Seek> Drop for StreamWriter<'a, T> {
+    fn drop(&mut self) {
+        let _ = self.flush_buffer();
+    }
 }
 
-// These functions will be passed to the OpenEXR C++ API.
+// These functions are passed to the OpenEXR C++ API as raw function
+// pointers, with a `*mut StreamWriter<T>` as their opaque context.
 
-/// Returns 0 on success and 1 on failure.
+/// Returns 0 on success, 1 on system failure or on a write refused for
+/// exceeding `max_bytes`, and 2 on other failure.
 ///
 /// ImfIO.h:
 /// virtual void write (const char c[/*n*/], int n) = 0;
-pub extern "C" fn write<T: Write + Seek>(stream_writer: *mut StreamWriter<T>,
-                                         c: *const c_char,
-                                         n: c_int)
-                                         -> c_int {
-    let bytes = unsafe { slice::from_raw_parts(c as *const u8, n as usize) };
-    if let Ok(_) = unsafe { (*stream_writer).writer.write_all(bytes) } {
-        unsafe { (*stream_writer).cursor_pos += n as usize };
-        return 0;
-    } else {
-        return 1;
+pub unsafe extern "C" fn write<T: Write + Seek>(
+    context: *mut c_void,
+    c: *const c_char,
+    n: c_int,
+    err_out: *mut c_int,
+) -> c_int {
+    let stream_writer = &mut *(context as *mut StreamWriter<T>);
+    let bytes = slice::from_raw_parts(c as *const u8, n as usize);
+
+    if let Some(max_bytes) = stream_writer.max_bytes {
+        let attempted = ::std::cmp::max(
+            stream_writer.high_water_mark,
+            stream_writer.cursor_pos + n as u64,
+        );
+        if attempted > max_bytes {
+            stream_writer.limit_exceeded = Some((max_bytes, attempted));
+            return 1;
+        }
     }
-}
 
-/// ImfIO.h:
-/// virtual Int64 tellp () = 0;
-pub extern "C" fn tellp<T: Write + Seek>(stream_writer: *mut StreamWriter<T>) -> i64 {
-    unsafe { (*stream_writer).cursor_pos as i64 }
+    let is_contiguous = stream_writer.buffer_capacity > 0
+        && stream_writer.cursor_pos == stream_writer.buffer_origin + stream_writer.buffer.len() as u64;
+
+    let result = if is_contiguous {
+        stream_writer.buffer.extend_from_slice(bytes);
+        if stream_writer.buffer.len() >= stream_writer.buffer_capacity {
+            stream_writer.flush_buffer()
+        } else {
+            Ok(())
+        }
+    } else {
+        stream_writer
+            .flush_buffer()
+            .and_then(|_| stream_writer.writer.write_all(bytes))
+    };
+
+    match result {
+        Ok(_) => {
+            stream_writer.cursor_pos += n as u64;
+            if stream_writer.buffer.is_empty() {
+                stream_writer.buffer_origin = stream_writer.cursor_pos;
+            }
+            stream_writer.high_water_mark =
+                ::std::cmp::max(stream_writer.high_water_mark, stream_writer.cursor_pos);
+            0
+        }
+        Err(e) => {
+            *err_out = e.raw_os_error().unwrap_or(0);
+            stream_writer.record_error(e)
+        }
+    }
 }
 
-/// Returns 0 on success and 1 on failure.
+/// Returns 0 on success, 1 on system failure, and 2 on other failure.
 ///
 /// ImfIO.h:
 /// virtual void seekp (Int64 pos) = 0;
-pub extern "C" fn seekp<T: Write + Seek>(stream_writer: *mut StreamWriter<T>, pos: i64) -> c_int {
-    if let Ok(new_pos) = unsafe { (*stream_writer).writer.seek(SeekFrom::Start(pos as u64)) } {
-        unsafe { (*stream_writer).cursor_pos = new_pos as usize };
+pub unsafe extern "C" fn seekp<T: Write + Seek>(
+    context: *mut c_void,
+    pos: u64,
+    err_out: *mut c_int,
+) -> c_int {
+    let stream_writer = &mut *(context as *mut StreamWriter<T>);
+
+    // OpenEXR seeks backward to patch the offset table and header after
+    // writing the bulk of the data; anything still buffered needs to hit
+    // the underlying writer before we move its cursor out from under it.
+    // Seeking to the current position moves nothing, so it keeps the buffer.
+    if pos == stream_writer.cursor_pos {
         return 0;
-    } else {
-        return 1;
+    }
+    if let Err(e) = stream_writer.flush_buffer() {
+        *err_out = e.raw_os_error().unwrap_or(0);
+        return stream_writer.record_error(e);
+    }
+
+    match stream_writer.writer.seek(SeekFrom::Start(pos)) {
+        Ok(new_pos) => {
+            stream_writer.cursor_pos = new_pos;
+            stream_writer.buffer_origin = new_pos;
+            0
+        }
+        Err(e) => {
+            *err_out = e.raw_os_error().unwrap_or(0);
+            stream_writer.record_error(e)
+        }
     }
 }
@@ -0,0 +1,298 @@
+use std::ffi::CStr;
+use std::io::{Seek, Write};
+use std::marker::PhantomData;
+use std::ptr;
+
+use libc::c_int;
+
+use openexr_sys::*;
+
+use cexr_type_aliases::Box2i;
+use error::*;
+use frame_buffer::FrameBuffer;
+use stream_io::{seek_stream, write_stream};
+use Header;
+
+/// Writes tiled OpenEXR files, including mip/rip-map images.
+///
+/// Unlike `ScanlineOutputFile`, data is written one tile at a time rather
+/// than one (or more) scanlines at a time, and a file can contain multiple
+/// resolution levels.  `header` must already have a tile description set
+/// via `Header::set_tile_description()` before being passed here.
+///
+/// # Examples
+///
+/// Write a single-level tiled floating point RGB image.
+///
+/// ```no_run
+/// # use openexr::{FrameBuffer, Header, LevelMode, PixelType, RoundingMode, TiledOutputFile};
+/// #
+/// let mut file = std::fs::File::create("output_file.exr").unwrap();
+/// let mut output_file = TiledOutputFile::new(
+///     &mut file,
+///     Header::new()
+///         .set_resolution(256, 256)
+///         .set_tile_description(64, 64, LevelMode::OneLevel, RoundingMode::RoundDown)
+///         .add_channel("R", PixelType::FLOAT)
+///         .add_channel("G", PixelType::FLOAT)
+///         .add_channel("B", PixelType::FLOAT))
+///     .unwrap();
+///
+/// let pixel_data = vec![(0.5f32, 1.0f32, 0.5f32); 256 * 256];
+/// let mut fb = FrameBuffer::new(256, 256);
+/// fb.insert_channels(&["R", "G", "B"], &pixel_data);
+/// // 256x256 pixels in 64x64 tiles is 4x4 tiles: write all of them.
+/// output_file.write_tiles((0, 3), (0, 3), (0, 0), &fb).unwrap();
+/// ```
+pub struct TiledOutputFile<'a> {
+    handle: *mut CEXR_TiledOutputFile,
+    header_ref: Header,
+    ostream: *mut CEXR_OStream,
+    _phantom_1: PhantomData<CEXR_TiledOutputFile>,
+    _phantom_2: PhantomData<&'a mut ()>, // Represents the borrowed writer
+
+    // NOTE: Because we don't know what type the writer might be, it's important
+    // that this struct remains neither Sync nor Send.  Please don't implement
+    // them!
+}
+
+impl<'a> TiledOutputFile<'a> {
+    /// Creates a new `TiledOutputFile` from any `Write + Seek` type
+    /// (typically a `std::fs::File`) and `header`.
+    ///
+    /// Note: this seeks to byte 0 before writing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `header` doesn't have a tile description set.
+    pub fn new<T: 'a>(writer: &'a mut T, header: &Header) -> Result<TiledOutputFile<'a>>
+    where
+        T: Write + Seek,
+    {
+        if !header.is_tiled() {
+            return Err(Error::Unsupported(
+                "header does not have a tile description: call \
+                 Header::set_tile_description() before creating a TiledOutputFile"
+                    .to_string(),
+            ));
+        }
+
+        let ostream_ptr = {
+            let write_ptr = write_stream::<T>;
+            let seekp_ptr = seek_stream::<T>;
+
+            let mut error_out = ptr::null();
+            let mut out = ptr::null_mut();
+            let error = unsafe {
+                CEXR_OStream_from_writer(
+                    writer as *mut T as *mut _,
+                    Some(write_ptr),
+                    Some(seekp_ptr),
+                    &mut out,
+                    &mut error_out,
+                )
+            };
+
+            if error != 0 {
+                let msg = unsafe { CStr::from_ptr(error_out) };
+                return Err(Error::C(msg.to_string_lossy().into_owned()));
+            } else {
+                out
+            }
+        };
+
+        let mut error_out = ptr::null();
+        let mut out = ptr::null_mut();
+        let error = unsafe {
+            // NOTE: we don't need to keep a copy of the header, because this
+            // function makes a deep copy that is stored in the
+            // CEXR_TiledOutputFile.
+            CEXR_TiledOutputFile_from_stream(ostream_ptr, header.handle, 1, &mut out, &mut error_out)
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(TiledOutputFile {
+                handle: out,
+                header_ref: Header {
+                    // NOTE: We're casting to *mut here to satisfy the
+                    // field's type, but importantly we only return a
+                    // const & of the Header so it retains const semantics.
+                    handle: unsafe { CEXR_TiledOutputFile_header(out) } as *mut CEXR_Header,
+                    owned: false,
+                    _phantom: PhantomData,
+                },
+                ostream: ostream_ptr,
+                _phantom_1: PhantomData,
+                _phantom_2: PhantomData,
+            })
+        }
+    }
+
+    /// Writes a single tile from `framebuffer`.
+    ///
+    /// `level` is the `(x, y)` mip/rip-map level to write to; for
+    /// single-level (non-mipmapped) tiled images this is always `(0, 0)`.
+    /// `dx`/`dy` are the tile's coordinates within that level, in tiles
+    /// (not pixels).
+    ///
+    /// # Errors
+    ///
+    /// This function expects `framebuffer`'s channels to match the header's
+    /// channels in type and subsampling.  It will also return an error if
+    /// there is an I/O error.
+    pub fn write_tile(
+        &mut self,
+        dx: u32,
+        dy: u32,
+        level: (u32, u32),
+        framebuffer: &FrameBuffer,
+    ) -> Result<()> {
+        self.header().validate_framebuffer_for_output(framebuffer)?;
+
+        let mut error_out = ptr::null();
+
+        let error = unsafe {
+            CEXR_TiledOutputFile_set_framebuffer(self.handle, framebuffer.handle(), &mut error_out)
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        let error = unsafe {
+            CEXR_TiledOutputFile_write_tile(
+                self.handle,
+                dx as c_int,
+                dy as c_int,
+                level.0 as c_int,
+                level.1 as c_int,
+                &mut error_out,
+            )
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            Err(Error::C(msg.to_string_lossy().into_owned()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes a rectangular range of tiles, from `dx_range.0` to
+    /// `dx_range.1` and `dy_range.0` to `dy_range.1` inclusive, at `level`.
+    ///
+    /// Equivalent to calling `write_tile()` for each tile in the range, but
+    /// only sets `framebuffer` once rather than once per tile.
+    ///
+    /// # Errors
+    ///
+    /// This function expects `framebuffer`'s channels to match the header's
+    /// channels in type and subsampling.  It will also return an error if
+    /// there is an I/O error.
+    pub fn write_tiles(
+        &mut self,
+        dx_range: (u32, u32),
+        dy_range: (u32, u32),
+        level: (u32, u32),
+        framebuffer: &FrameBuffer,
+    ) -> Result<()> {
+        self.header().validate_framebuffer_for_output(framebuffer)?;
+
+        let mut error_out = ptr::null();
+
+        let error = unsafe {
+            CEXR_TiledOutputFile_set_framebuffer(self.handle, framebuffer.handle(), &mut error_out)
+        };
+        if error != 0 {
+            let msg = unsafe { CStr::from_ptr(error_out) };
+            return Err(Error::C(msg.to_string_lossy().into_owned()));
+        }
+
+        for dy in dy_range.0..=dy_range.1 {
+            for dx in dx_range.0..=dx_range.1 {
+                let error = unsafe {
+                    CEXR_TiledOutputFile_write_tile(
+                        self.handle,
+                        dx as c_int,
+                        dy as c_int,
+                        level.0 as c_int,
+                        level.1 as c_int,
+                        &mut error_out,
+                    )
+                };
+                if error != 0 {
+                    let msg = unsafe { CStr::from_ptr(error_out) };
+                    return Err(Error::C(msg.to_string_lossy().into_owned()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of tiles in the x and y directions at `level`.
+    pub fn level_dimensions(&self, level: (u32, u32)) -> (u32, u32) {
+        let mut x = 0;
+        let mut y = 0;
+        unsafe {
+            CEXR_TiledOutputFile_level_dimensions(
+                self.handle,
+                level.0 as c_int,
+                level.1 as c_int,
+                &mut x,
+                &mut y,
+            )
+        };
+        (x as u32, y as u32)
+    }
+
+    /// Returns the number of levels in the x direction.
+    ///
+    /// For `LevelMode::OneLevel` and `LevelMode::MipmapLevels` images this
+    /// is the same as `num_y_levels()`; for `LevelMode::RipmapLevels`
+    /// images it may differ.
+    pub fn num_x_levels(&self) -> u32 {
+        unsafe { CEXR_TiledOutputFile_num_x_levels(self.handle) as u32 }
+    }
+
+    /// Returns the number of levels in the y direction.
+    pub fn num_y_levels(&self) -> u32 {
+        unsafe { CEXR_TiledOutputFile_num_y_levels(self.handle) as u32 }
+    }
+
+    /// Returns the `(x, y)` tile size, in pixels.
+    ///
+    /// Convenience wrapper around `Header::tile_size()`, which is always
+    /// `Some` for a file this type was able to open.
+    pub fn tile_size(&self) -> (u32, u32) {
+        self.header()
+            .tile_size()
+            .expect("a successfully-opened TiledOutputFile's header always has a tile description")
+    }
+
+    /// Returns the `(x, y)` number of levels, i.e. `(num_x_levels(),
+    /// num_y_levels())`.
+    pub fn num_levels(&self) -> (u32, u32) {
+        (self.num_x_levels(), self.num_y_levels())
+    }
+
+    /// Returns the data window, in pixels, of the given level.
+    pub fn level_data_window(&self, level: (u32, u32)) -> Box2i {
+        unsafe {
+            CEXR_TiledOutputFile_level_data_window(self.handle, level.0 as c_int, level.1 as c_int)
+        }
+    }
+
+    /// Access to the file's header.
+    pub fn header(&self) -> &Header {
+        &self.header_ref
+    }
+}
+
+impl<'a> Drop for TiledOutputFile<'a> {
+    fn drop(&mut self) {
+        unsafe { CEXR_TiledOutputFile_delete(self.handle) };
+        unsafe { CEXR_OStream_delete(self.ostream) };
+    }
+}
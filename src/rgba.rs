@@ -0,0 +1,150 @@
+//! High-level convenience functions for the common case of reading or
+//! writing a plain RGBA image, without dealing with `FrameBuffer`s or
+//! `PixelType`s directly.
+
+use std::io::{Read, Seek, Write};
+use std::mem;
+use std::os::raw::c_char;
+
+use error::Result;
+use frame_buffer::{FrameBuffer, FrameBufferMut, PixelStruct};
+use input::InputFile;
+use output::ScanlineOutputFile;
+use Compression;
+use Header;
+use PixelType;
+
+/// An RGBA pixel as stored in the buffers this module, and
+/// `image_support`, read into and write out of.
+pub(crate) type RgbaPixel = (f32, f32, f32, f32);
+
+/// Returns the pixel offset, within a buffer whose pixel `0` is the data
+/// window's corner `origin`, of the data-window-absolute coordinate
+/// `(0, 0)`.
+///
+/// OpenEXR addresses scanlines and columns using the data window's own
+/// absolute coordinates against the framebuffer's base pointer, so a
+/// plain 0-origin buffer needs its effective base shifted by this many
+/// elements before being handed to `InputFile::read_pixels` -- otherwise
+/// a file whose data window doesn't start at `(0, 0)` (the common case
+/// for a cropped data window) gets read at the wrong addresses entirely.
+pub(crate) fn calc_origin_offset(image_width: u32, origin: (i32, i32)) -> isize {
+    let (x, y) = origin;
+    -(x as isize + y as isize * image_width as isize)
+}
+
+/// Builds a `FrameBufferMut` over `pixels` with `R`/`G`/`B`/`A` channels,
+/// shifted by `origin` (see `calc_origin_offset`) so it can be read into
+/// directly by `InputFile::read_pixels` regardless of the file's data
+/// window origin.
+///
+/// `pixels` must have exactly `width * height` elements, one per pixel of
+/// the data window, in row-major order starting at `origin`.
+pub(crate) fn rgba_frame_buffer_mut(
+    pixels: &mut [RgbaPixel],
+    width: u32,
+    height: u32,
+    origin: (i32, i32),
+) -> FrameBufferMut {
+    let mut fb = FrameBufferMut::new(width, height);
+    let element_size = mem::size_of::<RgbaPixel>();
+    let row_stride = width as usize * element_size;
+    let base = unsafe {
+        (pixels.as_mut_ptr() as *mut c_char)
+            .offset(calc_origin_offset(width, origin) * element_size as isize)
+    };
+    let names_and_fills = [("R", 0.0), ("G", 0.0), ("B", 0.0), ("A", 1.0)];
+    for (&(name, fill), (ty, field_offset)) in names_and_fills.iter().zip(RgbaPixel::channels()) {
+        unsafe {
+            fb.insert_raw(
+                name,
+                ty,
+                base.offset(field_offset as isize),
+                (element_size, row_stride),
+                (1, 1),
+                fill,
+                (false, false),
+            );
+        }
+    }
+    fb
+}
+
+/// Reads the `R`, `G`, `B`, and `A` channels of an OpenEXR file from `reader`
+/// as `f32` values, converting from whatever `PixelType` each channel is
+/// actually stored as.
+///
+/// Color channels default to `0.0` and the alpha channel defaults to fully
+/// opaque (`1.0`) if they aren't present in the file.
+///
+/// Returns the image's dimensions and its pixel data in row-major order.
+///
+/// # Errors
+///
+/// Returns an error if there is an I/O error, or if `reader` isn't a valid
+/// OpenEXR file.
+pub fn read_rgba_image<T: Read + Seek>(
+    reader: &mut T,
+) -> Result<(u32, u32, Vec<(f32, f32, f32, f32)>)> {
+    let mut exr_file = InputFile::new(reader)?;
+    let (width, height) = exr_file.header().data_dimensions();
+    let origin = exr_file.header().data_origin();
+
+    let mut pixel_data = vec![(0.0f32, 0.0f32, 0.0f32, 1.0f32); (width * height) as usize];
+    {
+        let mut fb = rgba_frame_buffer_mut(&mut pixel_data, width, height, origin);
+        exr_file.read_pixels(&mut fb)?;
+    }
+
+    Ok((width, height, pixel_data))
+}
+
+/// Writes `pixel_data` -- `(R, G, B, A)` tuples in row-major order -- to
+/// `writer` as a `width` x `height` floating-point OpenEXR image, using
+/// `Compression::ZIP_COMPRESSION`.
+///
+/// For more control over the channel types, compression method, or other
+/// header properties, build a `Header` and use `ScanlineOutputFile`
+/// directly instead.
+///
+/// # Panics
+///
+/// Panics if `pixel_data.len() != width as usize * height as usize`.
+///
+/// # Errors
+///
+/// Returns an error if there is an I/O error.
+pub fn write_rgba_image<T: Write + Seek>(
+    writer: &mut T,
+    width: u32,
+    height: u32,
+    pixel_data: &[(f32, f32, f32, f32)],
+) -> Result<()> {
+    assert_eq!(
+        pixel_data.len(),
+        width as usize * height as usize,
+        "pixel data of {} elements does not match {}x{} image dimensions",
+        pixel_data.len(),
+        width,
+        height
+    );
+
+    let mut header = Header::new();
+    header
+        .set_resolution(width, height)
+        .set_compression(Compression::ZIP_COMPRESSION)
+        .add_channel("R", PixelType::FLOAT)
+        .add_channel("G", PixelType::FLOAT)
+        .add_channel("B", PixelType::FLOAT)
+        .add_channel("A", PixelType::FLOAT);
+
+    let mut exr_file = ScanlineOutputFile::new(writer, &header)?;
+
+    let fb = {
+        let mut fb = FrameBuffer::new(width, height);
+        fb.insert_channels(&["R", "G", "B", "A"], pixel_data);
+        fb
+    };
+
+    exr_file.write_pixels(&fb)
+}
@@ -18,9 +18,16 @@ use error::{Error, Result};
 ///
 /// If set to `0`, the thread pool is disabled and all OpenEXR calls will run
 /// on their calling thread.
+///
+/// This controls a pool that is global to the whole process, shared by
+/// every `InputFile`/`ScanlineOutputFile`/etc. opened afterwards -- it is
+/// not a per-file setting. Call it once, early (e.g. at program start),
+/// before opening any files: `read_pixels`/`read_pixels_partial` decode
+/// scanline blocks in parallel across the pool automatically once it's
+/// sized, with no other API changes required.
 pub fn set_global_thread_count(thread_count: usize) -> Result<()> {
     if thread_count > ::std::os::raw::c_int::max_value() as usize {
-        return Err(Error::Generic(String::from(
+        return Err(Error::Unsupported(String::from(
             "The number of threads is too high",
         )));
     }
@@ -0,0 +1,40 @@
+extern crate openexr;
+
+use openexr::InputFile;
+
+/// Feeds a variety of truncated/garbage byte slices through
+/// `InputFile::from_slice` (and the bounds-checked
+/// `InputFile::from_untrusted_slice`) and asserts that we get a clean
+/// `Error` back rather than a panic or UB, regardless of how malformed the
+/// input is.
+#[test]
+fn fuzz_resilience_garbage_input() {
+    let inputs: Vec<Vec<u8>> = vec![
+        Vec::new(),
+        vec![0u8; 1],
+        vec![0u8; 4],
+        vec![0xffu8; 64],
+        // Valid magic number, followed by garbage.
+        vec![0x76, 0x2f, 0x31, 0x01, 0, 0, 0, 0],
+        (0..4096).map(|i| (i % 256) as u8).collect(),
+    ];
+
+    for input in &inputs {
+        assert!(InputFile::from_slice(input).is_err());
+        assert!(InputFile::from_untrusted_slice(input).is_err());
+    }
+}
+
+/// Truncating a header-like byte sequence at every possible byte offset
+/// should never panic, even though none of the truncations is a valid file.
+#[test]
+fn fuzz_resilience_truncated_header() {
+    // A plausible-looking (but not actually valid) EXR header prefix: magic
+    // number + version, then garbage attribute data.
+    let mut data = vec![0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0];
+    data.extend((0..256).map(|i| (i * 7 % 256) as u8));
+
+    for len in 0..data.len() {
+        let _ = InputFile::from_slice(&data[..len]);
+    }
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "image")]
+
+extern crate image;
+extern crate openexr;
+
+use std::io::Cursor;
+
+use image::{DynamicImage, Rgba32FImage};
+use openexr::image_support::{read_rgba, write_rgba};
+
+#[test]
+fn image_support_round_trip() {
+    let mut source = Rgba32FImage::new(4, 3);
+    for y in 0..3 {
+        for x in 0..4 {
+            source.put_pixel(x, y, image::Rgba([x as f32 * 0.1, y as f32 * 0.1, 0.5, 1.0]));
+        }
+    }
+    let source = DynamicImage::ImageRgba32F(source);
+
+    let mut in_memory_buffer = Cursor::new(Vec::<u8>::new());
+    write_rgba(&mut in_memory_buffer, &source).unwrap();
+
+    let mut read_buffer = Cursor::new(in_memory_buffer.into_inner());
+    let decoded = read_rgba(&mut read_buffer).unwrap();
+
+    let expected = source.to_rgba32f();
+    let actual = decoded.to_rgba32f();
+    assert_eq!(actual.dimensions(), expected.dimensions());
+    for (expected_pixel, actual_pixel) in expected.pixels().zip(actual.pixels()) {
+        for channel in 0..4 {
+            assert!((expected_pixel[channel] - actual_pixel[channel]).abs() < 0.001);
+        }
+    }
+}
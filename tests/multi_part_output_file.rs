@@ -0,0 +1,64 @@
+extern crate openexr;
+
+use std::io::Cursor;
+
+use openexr::{FrameBuffer, FrameBufferMut, Header, MultiPartInputFile, MultiPartOutputFile, PixelType};
+
+fn part_header(width: u32, height: u32) -> Header {
+    let mut header = Header::new();
+    header
+        .set_resolution(width, height)
+        .add_channel("R", PixelType::FLOAT)
+        .add_channel("G", PixelType::FLOAT)
+        .add_channel("B", PixelType::FLOAT);
+    header
+}
+
+#[test]
+fn multi_part_round_trip() {
+    let mut in_memory_buffer = Cursor::new(Vec::<u8>::new());
+
+    {
+        let headers = [part_header(16, 8), part_header(4, 4)];
+        let mut exr_file = MultiPartOutputFile::new(&mut in_memory_buffer, &headers).unwrap();
+
+        for part in 0..2 {
+            let (width, height) = exr_file.header(part).data_dimensions();
+            let pixel_data = vec![(0.25f32, 0.5f32, 0.75f32); (width * height) as usize];
+            let mut fb = FrameBuffer::new(width, height);
+            fb.insert_channels(&["R", "G", "B"], &pixel_data);
+            exr_file.write_pixels(part, &fb).unwrap();
+        }
+    }
+
+    let mut read_buffer = Cursor::new(in_memory_buffer.into_inner());
+    let mut exr_file = MultiPartInputFile::new(&mut read_buffer).unwrap();
+    assert_eq!(exr_file.parts(), 2);
+
+    for part in 0..2 {
+        let (width, height) = exr_file.header(part).data_dimensions();
+        let mut pixel_data = vec![(0.0f32, 0.0f32, 0.0f32); (width * height) as usize];
+        let mut fb = FrameBufferMut::new(width, height);
+        fb.insert_channels(&[("R", 0.0), ("G", 0.0), ("B", 0.0)], &mut pixel_data);
+        exr_file.read_pixels(part, &mut fb).unwrap();
+        for pixel in pixel_data {
+            assert_eq!(pixel, (0.25, 0.5, 0.75));
+        }
+    }
+}
+
+/// A framebuffer that's shorter than a part's data window must be rejected
+/// up front, rather than letting OpenEXR read past the end of it.
+#[test]
+fn multi_part_write_pixels_rejects_short_framebuffer() {
+    let mut in_memory_buffer = Cursor::new(Vec::<u8>::new());
+    let headers = [part_header(16, 8)];
+    let mut exr_file = MultiPartOutputFile::new(&mut in_memory_buffer, &headers).unwrap();
+
+    // One scanline short of the part's data window.
+    let pixel_data = vec![(0.0f32, 0.0f32, 0.0f32); 16 * 7];
+    let mut fb = FrameBuffer::new(16, 7);
+    fb.insert_channels(&["R", "G", "B"], &pixel_data);
+
+    assert!(exr_file.write_pixels(0, &fb).is_err());
+}
@@ -0,0 +1,64 @@
+extern crate openexr;
+
+use std::io::Cursor;
+
+use openexr::{FrameBuffer, Header, InputFile, PixelType, SamplingMode, ScanlineOutputFile};
+
+fn channel_values(buf: &openexr::AllChannelsFrameBuffer, name: &str) -> Vec<f32> {
+    let (_, _, _, bytes) = buf.channels().find(|&(n, _, _, _)| n == name).unwrap();
+    bytes
+        .chunks(4)
+        .map(|c| f32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+#[test]
+fn read_pixels_sampled_clamp_extends_edges() {
+    let mut in_memory_buffer = Cursor::new(Vec::<u8>::new());
+
+    // A small 4x4 data window, with a distinct value at every pixel so we
+    // can tell the data window's placement within the sampled region apart
+    // from its clamped edges.
+    let pixel_data: Vec<f32> = (0..4)
+        .flat_map(|row| (0..4).map(move |col| (col + row * 10) as f32))
+        .collect();
+    {
+        let mut exr_file = ScanlineOutputFile::new(
+            &mut in_memory_buffer,
+            Header::new()
+                .set_resolution(4, 4)
+                .add_channel("Z", PixelType::FLOAT),
+        )
+        .unwrap();
+        let mut fb = FrameBuffer::new(4, 4);
+        fb.insert_channel("Z", &pixel_data);
+        exr_file.write_pixels(&fb).unwrap();
+    }
+
+    let mut exr_file = InputFile::from_slice(in_memory_buffer.get_ref()).unwrap();
+
+    // Request a region two pixels larger than the data window on every
+    // side, so the data window ends up placed at offset (2, 2) within it.
+    let region = Header::box2i(-2, -2, 8, 8);
+    let buffer = exr_file
+        .read_pixels_sampled(region, SamplingMode::Clamp)
+        .unwrap();
+    let values = channel_values(&buffer, "Z");
+
+    let at = |x: i32, y: i32| values[((x + 2) + (y + 2) * 8) as usize];
+
+    // Pixels inside the data window are read through unchanged.
+    for row in 0..4 {
+        for col in 0..4 {
+            assert_eq!(at(col, row), (col + row * 10) as f32);
+        }
+    }
+
+    // Pixels outside the data window replicate the nearest edge pixel.
+    assert_eq!(at(-1, 0), at(0, 0));
+    assert_eq!(at(4, 0), at(3, 0));
+    assert_eq!(at(0, -1), at(0, 0));
+    assert_eq!(at(0, 4), at(0, 3));
+    assert_eq!(at(-2, -2), at(0, 0));
+    assert_eq!(at(5, 5), at(3, 3));
+}
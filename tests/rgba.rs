@@ -0,0 +1,24 @@
+extern crate openexr;
+
+use std::io::Cursor;
+
+use openexr::{read_rgba_image, write_rgba_image};
+
+#[test]
+fn rgba_image_round_trip() {
+    let (width, height) = (5, 3);
+    let pixel_data: Vec<(f32, f32, f32, f32)> = (0..width * height)
+        .map(|i| (i as f32 * 0.1, i as f32 * 0.2, i as f32 * 0.3, 1.0))
+        .collect();
+
+    let mut in_memory_buffer = Cursor::new(Vec::<u8>::new());
+    write_rgba_image(&mut in_memory_buffer, width, height, &pixel_data).unwrap();
+
+    let mut read_buffer = Cursor::new(in_memory_buffer.into_inner());
+    let (read_width, read_height, read_pixel_data) = read_rgba_image(&mut read_buffer).unwrap();
+
+    assert_eq!((read_width, read_height), (width, height));
+    for (expected, actual) in pixel_data.iter().zip(read_pixel_data.iter()) {
+        assert_eq!(expected, actual);
+    }
+}